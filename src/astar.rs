@@ -0,0 +1,303 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::atomic::AtomicBool,
+};
+
+use crate::board::{Block, BlockType, NodeValue};
+use crate::game::{Game, Metric, Rules};
+use crate::goal::Goal;
+use crate::solve::{Solution, SolveError, Solver, check_cancel};
+
+/// 到终局步数的下界, 满足一致性, A* 中每个局面第一次出队时即为最短
+///
+/// 终局为图案时不估计, 退化为按步数顺序搜索.
+pub(crate) fn heuristic(state: &NodeValue, rules: &Rules) -> usize {
+    match rules.goal {
+        Goal::Exit => exit_heuristic(state, rules),
+        Goal::Piece { ty, x, y } => piece_heuristic(state, rules, ty, (x, y)),
+        Goal::Pattern(_) => 0,
+    }
+}
+
+/// 同类棋子中离目标最近的一个到目标的步数下界
+///
+/// 棋子中 x + y (或其他方向的组合) 最大的格子每移动 1 都进入一个原来不属于棋子的空格, 所以一步移动的曼哈顿距离不超过空格数.
+fn piece_heuristic(
+    state: &NodeValue,
+    rules: &Rules,
+    ty: BlockType,
+    (x, y): (usize, usize),
+) -> usize {
+    let game = Game::new_unchecked(state, rules);
+    let step = match rules.metric {
+        Metric::Cell => 1,
+        Metric::Slide | Metric::Piece => game.empty_cell.len().max(1),
+    };
+    game.blocks
+        .iter()
+        .filter(|e| e.ty == ty)
+        .map(|e| (e.x.abs_diff(x) + e.y.abs_diff(y)).div_ceil(step))
+        .min()
+        .unwrap_or(0)
+}
+
+/// 曹操到出口的步数下界, 加上占据出口格子的其他棋子数, 有两个出口时取较小的一个
+///
+/// 自定义形状的棋子不区分个数, 出口上有任意格子属于自定义形状时只算一个.
+/// 曹操每步移动的曼哈顿距离不超过 max_cao_distance, 出口上的每个其他棋子至少要移动一次, 两者互不重叠, 所以不会高估.
+/// 一步移动最多让其中一项减少 1, 因此也满足一致性.
+fn exit_heuristic(state: &NodeValue, rules: &Rules) -> usize {
+    let (width, height) = state.size();
+    let gy = height - 2;
+    let cells = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+    let cao = cells
+        .clone()
+        .find(|&(x, y)| state.get(x, y) == Some(BlockType::CaoCao));
+    let empty = cells.filter(|&(x, y)| state.get(x, y).is_none()).count();
+    let step = max_cao_distance(rules.metric, empty);
+
+    let mut ret = usize::MAX;
+    for gx in state.exit_columns() {
+        let mut h = cao.map_or(0, |(x, y)| (x.abs_diff(gx) + y.abs_diff(gy)).div_ceil(step));
+        let mut blockers = Vec::with_capacity(4);
+        let mut custom = false;
+        for (x, y) in [(gx, gy), (gx + 1, gy), (gx, gy + 1), (gx + 1, gy + 1)] {
+            match anchor(state, x, y) {
+                Some(e) if matches!(e.ty, BlockType::Custom(_)) => custom = true,
+                Some(e) if e.ty != BlockType::CaoCao && !blockers.contains(&e) => blockers.push(e),
+                _ => {}
+            }
+        }
+        h += blockers.len() + custom as usize;
+        ret = ret.min(h);
+    }
+    ret
+}
+
+/// 曹操一步最多移动的曼哈顿距离
+///
+/// 移动 d 的路径上曹操扫过的格子至少有 2d + 4 个, 除曹操原来的 4 格外都必须是空格, 所以 d 不超过空格数的一半.
+fn max_cao_distance(metric: Metric, empty: usize) -> usize {
+    match metric {
+        Metric::Cell => 1,
+        Metric::Slide | Metric::Piece => (empty / 2).max(1),
+    }
+}
+
+/// 覆盖 (x, y) 的棋子, 同一行 (列) 连续的横向 (纵向) 方块从左 (上) 开始两两成对
+fn anchor(state: &NodeValue, x: usize, y: usize) -> Option<Block> {
+    let ty = state.get(x, y)?;
+    let (x, y) = match ty {
+        BlockType::Horizontal => {
+            let before = (0..x)
+                .rev()
+                .take_while(|&e| state.get(e, y) == Some(ty))
+                .count();
+            (x - before % 2, y)
+        }
+        BlockType::Vertical => {
+            let before = (0..y)
+                .rev()
+                .take_while(|&e| state.get(x, e) == Some(ty))
+                .count();
+            (x, y - before % 2)
+        }
+        //只用于区分出口上的不同棋子, 曹操只有一个, 自定义形状不区分
+        BlockType::CaoCao | BlockType::Pawn | BlockType::Custom(_) => (x, y),
+    };
+    Some(Block { ty, x, y })
+}
+
+/// A* 搜索
+///
+/// 只扩展 g + h 不超过最短步数的局面, 比广度优先搜索记录的局面少.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AStar {
+    pub rules: Rules,
+}
+
+impl Solver for AStar {
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution> {
+        Game::new(state, &self.rules)?;
+
+        //以镜像合并后的局面为键, 记录 g, 实际局面和上一个局面
+        let mut best: HashMap<NodeValue, (usize, NodeValue, Option<NodeValue>)> = HashMap::new();
+        let mut closed = HashSet::new();
+        //f 相同时优先扩展 g 较大的局面
+        let mut open = BinaryHeap::new();
+
+        best.insert(self.rules.key(state), (0, *state, None));
+        open.push((Reverse(heuristic(state, &self.rules)), 0, *state));
+
+        let mut next_nodes = Vec::new();
+        while let Some((_, g, current)) = open.pop() {
+            let key = self.rules.key(&current);
+            if !closed.insert(key) {
+                continue;
+            }
+            check_cancel(cancel)?;
+
+            if self.rules.is_finish(&current) {
+                let mut states = vec![current];
+                let mut prev = best[&key].2;
+                while let Some(e) = prev {
+                    states.push(e);
+                    prev = best[&self.rules.key(&e)].2;
+                }
+                states.reverse();
+                return Solution::from_states(&states, &self.rules, best.len());
+            }
+
+            Game::new_unchecked(&current, &self.rules).next_nodes(&mut next_nodes);
+            for (_, n) in next_nodes.drain(..) {
+                let key = self.rules.key(&n);
+                if closed.contains(&key) {
+                    continue;
+                }
+                if let Some((old, _, _)) = best.get(&key)
+                    && *old <= g + 1
+                {
+                    continue;
+                }
+                best.insert(key, (g + 1, n, Some(current)));
+                open.push((Reverse(g + 1 + heuristic(&n, &self.rules)), g + 1, n));
+            }
+
+            if best.len() >= limit {
+                return Err(SolveError::LimitExceeded(limit).into());
+            }
+        }
+
+        Err(SolveError::NoSolution.into())
+    }
+}
+
+/// 迭代加深 A* 搜索
+///
+/// 每轮深度优先搜索 g + h 不超过阈值的局面, 阈值逐轮增大到最短步数.
+/// 只保存当前路径和最多 table_size 个局面的置换表, 置换表满后不再记录, 只会变慢, 结果仍为最短.
+/// limit 限制的是扩展局面的总次数.
+#[derive(Clone, Copy, Debug)]
+pub struct IdaStar {
+    pub table_size: usize,
+    pub rules: Rules,
+}
+
+impl Default for IdaStar {
+    fn default() -> Self {
+        Self {
+            table_size: 1 << 16,
+            rules: Rules::default(),
+        }
+    }
+}
+
+struct IdaSearch<'a> {
+    cancel: &'a AtomicBool,
+    table_size: usize,
+    rules: Rules,
+    limit: usize,
+    explored: usize,
+    //本轮中到达每个局面时的最小 g
+    table: HashMap<NodeValue, usize>,
+    path: Vec<NodeValue>,
+}
+
+enum IdaResult {
+    Found,
+    //超过阈值的最小 f, 没有则为 None
+    Next(Option<usize>),
+}
+
+impl IdaSearch<'_> {
+    fn search(&mut self, g: usize, h: usize, threshold: usize) -> anyhow::Result<IdaResult> {
+        let current = *self.path.last().unwrap();
+        if g + h > threshold {
+            return Ok(IdaResult::Next(Some(g + h)));
+        }
+        if self.rules.is_finish(&current) {
+            return Ok(IdaResult::Found);
+        }
+
+        self.explored += 1;
+        if self.explored >= self.limit {
+            return Err(SolveError::LimitExceeded(self.limit).into());
+        }
+        check_cancel(self.cancel)?;
+
+        let mut next_nodes = Vec::new();
+        Game::new_unchecked(&current, &self.rules).next_nodes(&mut next_nodes);
+        let mut children: Vec<_> = next_nodes
+            .into_iter()
+            .map(|(_, e)| (heuristic(&e, &self.rules), e))
+            .collect();
+        children.sort_by_key(|e| e.0);
+
+        let prev = self.path.len().checked_sub(2).map(|e| self.path[e]);
+        let mut next = None;
+        for (h, n) in children {
+            if Some(n) == prev {
+                continue;
+            }
+            let key = self.rules.key(&n);
+            let full = self.table.len() >= self.table_size;
+            match self.table.get_mut(&key) {
+                Some(old) if *old <= g + 1 => continue,
+                Some(old) => *old = g + 1,
+                None if !full => {
+                    self.table.insert(key, g + 1);
+                }
+                None => {}
+            }
+
+            self.path.push(n);
+            match self.search(g + 1, h, threshold)? {
+                IdaResult::Found => return Ok(IdaResult::Found),
+                IdaResult::Next(Some(f)) => next = Some(next.map_or(f, |e: usize| e.min(f))),
+                IdaResult::Next(None) => {}
+            }
+            self.path.pop();
+        }
+        Ok(IdaResult::Next(next))
+    }
+}
+
+impl Solver for IdaStar {
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution> {
+        Game::new(state, &self.rules)?;
+
+        let mut search = IdaSearch {
+            cancel,
+            table_size: self.table_size,
+            rules: self.rules,
+            limit,
+            explored: 0,
+            table: HashMap::new(),
+            path: vec![*state],
+        };
+        let h = heuristic(state, &self.rules);
+        let mut threshold = h;
+        loop {
+            search.table.clear();
+            search.table.insert(self.rules.key(state), 0);
+            match search.search(0, h, threshold)? {
+                IdaResult::Found => {
+                    return Solution::from_states(&search.path, &self.rules, search.explored);
+                }
+                IdaResult::Next(Some(f)) => threshold = f,
+                IdaResult::Next(None) => return Err(SolveError::NoSolution.into()),
+            }
+        }
+    }
+}
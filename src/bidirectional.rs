@@ -0,0 +1,325 @@
+use std::{collections::HashMap, sync::atomic::AtomicBool};
+
+use crate::board::{Block, BlockType, MAX_CELLS, NodeValue, Shape};
+use crate::game::{Game, Move, Rules};
+use crate::goal::{Goal, Pattern};
+use crate::solve::{Solution, SolveError, Solver, check_cancel};
+
+/// 已访问局面, 以镜像合并后的局面为键, 记录实际局面和它的上一个局面
+type Visited = HashMap<NodeValue, (NodeValue, Option<NodeValue>)>;
+
+/// 双向广度优先搜索
+///
+/// 所有移动都可逆, 所以可以从全部终局同时反向搜索, 两边每次扩展较小的一层, 相遇时得到最短解.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bidirectional {
+    pub rules: Rules,
+}
+
+impl Solver for Bidirectional {
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution> {
+        solve(state, limit, &self.rules, cancel)
+    }
+}
+
+fn solve(
+    state: &NodeValue,
+    limit: usize,
+    rules: &Rules,
+    cancel: &AtomicBool,
+) -> anyhow::Result<Solution> {
+    let game = Game::new(state, rules)?;
+    if rules.is_finish(state) {
+        return Solution::from_states(&[*state], rules, 1);
+    }
+
+    let mut forward = Visited::new();
+    forward.insert(rules.key(state), (*state, None));
+    let mut forward_frontier = vec![*state];
+
+    //终局很多时只放入终局就可能超过上限, 所以逐个放入时检查
+    let Goals {
+        visited: mut backward,
+        frontier: mut backward_frontier,
+        ..
+    } = goal_states(&game, rules, forward.len(), limit, cancel)?;
+    if forward.len() + backward.len() >= limit {
+        return Err(SolveError::LimitExceeded(limit).into());
+    }
+
+    let mut next_nodes = Vec::new();
+    loop {
+        if forward_frontier.is_empty() || backward_frontier.is_empty() {
+            return Err(SolveError::NoSolution.into());
+        }
+
+        let meet = if forward_frontier.len() <= backward_frontier.len() {
+            expand(
+                &mut forward_frontier,
+                &mut forward,
+                &backward,
+                rules,
+                (limit, cancel),
+                &mut next_nodes,
+            )?
+        } else {
+            expand(
+                &mut backward_frontier,
+                &mut backward,
+                &forward,
+                rules,
+                (limit, cancel),
+                &mut next_nodes,
+            )?
+        };
+
+        if let Some(meet) = meet {
+            let mut states = chain(&forward, meet, rules);
+            states.reverse();
+            let head = *states.last().unwrap();
+
+            //两边在相遇点的方向可能互为镜像, 镜像后的反向路径同样到达终局
+            let tail = chain(&backward, meet, rules);
+            let mirrored = tail[0] != head;
+            states.extend(
+                tail.into_iter()
+                    .skip(1)
+                    .map(|e| if mirrored { e.mirror() } else { e }),
+            );
+            return Solution::from_states(&states, rules, forward.len() + backward.len());
+        }
+    }
+}
+
+/// 从 key 对应的局面沿记录的上一个局面回溯到起点
+fn chain(visited: &Visited, key: NodeValue, rules: &Rules) -> Vec<NodeValue> {
+    let (mut current, mut prev) = visited[&key];
+    let mut ret = vec![current];
+    while let Some(e) = prev {
+        current = e;
+        prev = visited[&rules.key(&current)].1;
+        ret.push(current);
+    }
+    ret
+}
+
+/// 扩展一整层, 新局面在另一边已访问过时返回相遇局面的键
+///
+/// 在此之前两边没有相遇, 说明最短解长于两边已搜索的深度之和, 因此这一层中任何相遇点都给出最短解.
+/// 每放入一个局面检查一次两边的局面总数, 达到 limit 时停止.
+fn expand(
+    frontier: &mut Vec<NodeValue>,
+    visited: &mut Visited,
+    other: &Visited,
+    rules: &Rules,
+    (limit, cancel): (usize, &AtomicBool),
+    next_nodes: &mut Vec<(Move, NodeValue)>,
+) -> Result<Option<NodeValue>, SolveError> {
+    let mut next_frontier = Vec::new();
+    for e in frontier.iter() {
+        check_cancel(cancel)?;
+        Game::new_unchecked(e, rules).next_nodes(next_nodes);
+        for (_, n) in next_nodes.drain(..) {
+            let key = rules.key(&n);
+            if visited.contains_key(&key) {
+                continue;
+            }
+            visited.insert(key, (n, Some(*e)));
+            if other.contains_key(&key) {
+                return Ok(Some(key));
+            }
+            if visited.len() + other.len() >= limit {
+                return Err(SolveError::LimitExceeded(limit));
+            }
+            next_frontier.push(n);
+        }
+    }
+    *frontier = next_frontier;
+    Ok(None)
+}
+
+/// 反向搜索的起点
+struct Goals<'a> {
+    rules: &'a Rules,
+    cancel: &'a AtomicBool,
+    //另一边已有的局面数量
+    forward: usize,
+    limit: usize,
+    visited: Visited,
+    frontier: Vec<NodeValue>,
+}
+
+impl Goals<'_> {
+    /// 放入一个逐格放置得到的局面
+    fn push(&mut self, state: NodeValue) -> Result<(), SolveError> {
+        check_cancel(self.cancel)?;
+        //逐格放置时按形状摆放, 解析局面时相邻的同类棋子可能被分成另一种组合, 所以再检查一次
+        if !self.rules.is_finish(&state) {
+            return Ok(());
+        }
+        if let std::collections::hash_map::Entry::Vacant(entry) =
+            self.visited.entry(self.rules.key(&state))
+        {
+            entry.insert((state, None));
+            self.frontier.push(state);
+        }
+        if self.forward + self.visited.len() >= self.limit {
+            return Err(SolveError::LimitExceeded(self.limit));
+        }
+        Ok(())
+    }
+}
+
+/// 与 game 棋子组成和棋盘大小相同, 且满足终局条件的所有局面, 与另一边合计达到 limit 时停止
+fn goal_states<'a>(
+    game: &Game,
+    rules: &'a Rules,
+    forward: usize,
+    limit: usize,
+    cancel: &'a AtomicBool,
+) -> Result<Goals<'a>, SolveError> {
+    //每种棋子的形状和数量
+    let mut pieces: Vec<(BlockType, Shape, usize)> = Vec::new();
+    for e in game.blocks.iter() {
+        match pieces.iter_mut().find(|p| p.0 == e.ty) {
+            Some(p) => p.2 += 1,
+            None => pieces.push((e.ty, rules.shapes.shape(e.ty), 1)),
+        }
+    }
+    pieces.sort_by_key(|e| e.0);
+    let occupied: usize = pieces.iter().map(|e| e.1.len() * e.2).sum();
+    let size = game.state.size();
+    let (width, height) = size;
+
+    //终局条件固定位置的棋子先放入, 其余逐格枚举
+    let seeds: Vec<Option<Block>> = match rules.goal {
+        Goal::Exit => game
+            .state
+            .exit_columns()
+            .map(|x| {
+                Some(Block {
+                    ty: BlockType::CaoCao,
+                    x,
+                    y: height - 2,
+                })
+            })
+            .collect(),
+        Goal::Piece { ty, x, y } => vec![Some(Block { ty, x, y })],
+        Goal::Pattern(_) => vec![None],
+    };
+    let pattern = match &rules.goal {
+        Goal::Pattern(e) => Some(e),
+        _ => None,
+    };
+    let mut empty_board = game.state;
+    for y in 0..height {
+        for x in 0..width {
+            empty_board.set(x, y, None);
+        }
+    }
+
+    let mut ret = Goals {
+        rules,
+        cancel,
+        forward,
+        limit,
+        visited: Visited::new(),
+        frontier: Vec::new(),
+    };
+    for seed in seeds {
+        let mut board = empty_board;
+        let mut filled = [false; MAX_CELLS];
+        let mut seeded = None;
+        if let Some(e) = seed {
+            let Some(i) = pieces.iter().position(|p| p.0 == e.ty) else {
+                continue;
+            };
+            let Some(cells) = pieces[i].1.place((e.x, e.y), size) else {
+                continue;
+            };
+            for (x, y) in cells {
+                board.set(x, y, Some(e.ty));
+                filled[y * width + x] = true;
+            }
+            pieces[i].2 -= 1;
+            seeded = Some(i);
+        }
+
+        fill(
+            0,
+            &mut board,
+            &mut filled,
+            &mut pieces,
+            width * height - occupied,
+            pattern,
+            &mut ret,
+        )?;
+        if let Some(i) = seeded {
+            pieces[i].2 += 1;
+        }
+    }
+    Ok(ret)
+}
+
+/// 按行优先顺序逐格决定: 留空, 或者放一个剩余的棋子, 使该格成为棋子形状的第一格
+fn fill(
+    cell: usize,
+    board: &mut NodeValue,
+    filled: &mut [bool; MAX_CELLS],
+    pieces: &mut [(BlockType, Shape, usize)],
+    empty: usize,
+    pattern: Option<&Pattern>,
+    ret: &mut Goals,
+) -> Result<(), SolveError> {
+    let size = board.size();
+    let width = size.0;
+    let Some(cell) = (cell..size.0 * size.1).find(|e| !filled[*e]) else {
+        return ret.push(*board);
+    };
+    let x = cell % width;
+    let y = cell / width;
+
+    if empty > 0 && pattern.is_none_or(|e| e.allows(x, y, None)) {
+        filled[cell] = true;
+        fill(cell + 1, board, filled, pieces, empty - 1, pattern, ret)?;
+        filled[cell] = false;
+    }
+
+    for i in 0..pieces.len() {
+        let (ty, shape, count) = pieces[i];
+        if count == 0 {
+            continue;
+        }
+        let (fx, fy) = shape.first();
+        let Some(cells) = x
+            .checked_sub(fx)
+            .zip(y.checked_sub(fy))
+            .and_then(|e| shape.place(e, size))
+        else {
+            continue;
+        };
+        if cells.clone().any(|(x, y)| {
+            filled[y * width + x] || pattern.is_some_and(|e| !e.allows(x, y, Some(ty)))
+        }) {
+            continue;
+        }
+
+        for (x, y) in cells.clone() {
+            filled[y * width + x] = true;
+            board.set(x, y, Some(ty));
+        }
+        pieces[i].2 -= 1;
+        fill(cell + 1, board, filled, pieces, empty, pattern, ret)?;
+        pieces[i].2 += 1;
+        for (x, y) in cells {
+            filled[y * width + x] = false;
+            board.set(x, y, None);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,672 @@
+use crate::game::Rules;
+use crate::goal::{Goal, Pattern};
+use crate::roster::{NamedPiece, Roster};
+use crate::validate::{Problem, ValidationError, check_blocks};
+
+/// 棋子类型
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BlockType {
+    /// 2x2 的曹操
+    CaoCao,
+    /// 2 格的横向方块
+    Horizontal,
+    /// 2 格的纵向方块
+    Vertical,
+    /// 1 格的小兵
+    Pawn,
+    /// 输入中定义的形状, 参数为格子编码 5..=7, 形状见 Shapes
+    Custom(u8),
+}
+
+/// 棋盘上的一个棋子, 坐标为它外接矩形左上角的位置
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Block {
+    pub ty: BlockType,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// 经典棋盘的宽
+pub const WIDTH: usize = 4;
+/// 经典棋盘的高
+pub const HEIGHT: usize = 5;
+/// 棋盘最多的格子数
+pub const MAX_CELLS: usize = 40;
+/// 棋盘宽高的上限
+pub const MAX_SIDE: usize = 15;
+
+impl BlockType {
+    /// 内置棋子的字母
+    pub fn letter(self) -> Option<char> {
+        match self {
+            BlockType::CaoCao => Some('c'),
+            BlockType::Horizontal => Some('h'),
+            BlockType::Vertical => Some('v'),
+            BlockType::Pawn => Some('p'),
+            BlockType::Custom(_) => None,
+        }
+    }
+
+    fn code(val: Option<BlockType>) -> u64 {
+        match val {
+            None => 0,
+            Some(BlockType::CaoCao) => 1,
+            Some(BlockType::Horizontal) => 2,
+            Some(BlockType::Vertical) => 3,
+            Some(BlockType::Pawn) => 4,
+            Some(BlockType::Custom(code)) => code as u64,
+        }
+    }
+
+    fn from_code(code: u64) -> Option<BlockType> {
+        match code {
+            1 => Some(BlockType::CaoCao),
+            2 => Some(BlockType::Horizontal),
+            3 => Some(BlockType::Vertical),
+            4 => Some(BlockType::Pawn),
+            5..=7 => Some(BlockType::Custom(code as u8)),
+            _ => None,
+        }
+    }
+}
+
+/// 棋子形状, 以外接矩形左上角为原点, 按行优先每格一位, 1 表示占据
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Shape {
+    width: u8,
+    height: u8,
+    mask: u64,
+}
+
+impl Shape {
+    /// width x height 的矩形
+    pub fn rect(width: usize, height: usize) -> Self {
+        Self {
+            width: width as u8,
+            height: height as u8,
+            mask: (1 << (width * height)) - 1,
+        }
+    }
+
+    /// 从 "#./##" 形式的掩码解析, 行之间用 / 分隔, # 表示占据, . 表示空
+    ///
+    /// 形状必须连通, 且第一行和第一列都有格子.
+    pub fn parse(mask: &str) -> anyhow::Result<Self> {
+        let rows: Vec<&str> = mask.split('/').map(|e| e.trim()).collect();
+        let width = rows.iter().map(|e| e.chars().count()).max().unwrap_or(0);
+        let height = rows.len();
+        anyhow::ensure!(
+            (1..=MAX_SIDE).contains(&width) && height <= MAX_SIDE && width * height <= 64,
+            "shape {} too large",
+            mask
+        );
+
+        let mut ret = Self {
+            width: width as u8,
+            height: height as u8,
+            mask: 0,
+        };
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '#' => ret.mask |= 1 << (y * width + x),
+                    '.' => {}
+                    _ => anyhow::bail!("unknown shape token {}", c),
+                }
+            }
+        }
+        anyhow::ensure!(
+            ret.cells().any(|e| e.1 == 0)
+                && ret.cells().any(|e| e.0 == 0)
+                && ret.cells().any(|e| e.1 == height - 1)
+                && ret.cells().any(|e| e.0 == width - 1),
+            "shape {} has empty border",
+            mask
+        );
+
+        //从第一格出发能到达所有格子
+        let mut reached = 1u64 << ret.cells().next().map_or(0, |(x, y)| y * width + x);
+        loop {
+            let mut next = reached;
+            for (x, y) in ret.cells() {
+                let neighbors = [
+                    (x > 0).then(|| (x - 1, y)),
+                    (x + 1 < width).then_some((x + 1, y)),
+                    (y > 0).then(|| (x, y - 1)),
+                    (y + 1 < height).then_some((x, y + 1)),
+                ];
+                if neighbors
+                    .into_iter()
+                    .flatten()
+                    .any(|(x, y)| reached & (1 << (y * width + x)) != 0)
+                {
+                    next |= 1 << (y * width + x);
+                }
+            }
+            if next == reached {
+                break;
+            }
+            reached = next;
+        }
+        anyhow::ensure!(reached == ret.mask, "shape {} is not connected", mask);
+        Ok(ret)
+    }
+
+    /// 外接矩形 (宽, 高)
+    pub fn size(&self) -> (usize, usize) {
+        (self.width as usize, self.height as usize)
+    }
+
+    /// 占据的格子数
+    pub fn len(&self) -> usize {
+        self.mask.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0
+    }
+
+    /// 按行优先顺序占据的格子
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + Clone {
+        let (width, mut mask) = (self.width as usize, self.mask);
+        std::iter::from_fn(move || {
+            let i = mask.trailing_zeros() as usize;
+            //去掉最低位的 1
+            mask &= mask.wrapping_sub(1);
+            (i < 64).then_some((i % width, i / width))
+        })
+    }
+
+    /// 行优先的第一格, 解析局面时从这一格确定棋子位置
+    pub fn first(&self) -> (usize, usize) {
+        let i = self.mask.trailing_zeros() as usize;
+        (i % self.width as usize, i / self.width as usize)
+    }
+
+    /// 是否左右对称, 不对称的形状镜像后是另一种棋子
+    pub fn is_symmetric(&self) -> bool {
+        let width = self.width as usize;
+        self.cells()
+            .all(|(x, y)| self.mask & (1 << (y * width + width - 1 - x)) != 0)
+    }
+
+    /// 以 (x, y) 为外接矩形左上角时占据的格子, 越界时为 None
+    pub fn place(
+        &self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+    ) -> Option<impl Iterator<Item = (usize, usize)> + Clone> {
+        let fits =
+            |pos: usize, len: u8, max| pos.checked_add(len as usize).is_some_and(|e| e <= max);
+        (fits(x, self.width, width) && fits(y, self.height, height))
+            .then(|| self.cells().map(move |(dx, dy)| (x + dx, y + dy)))
+    }
+}
+
+impl std::fmt::Display for Shape {
+    /// 与 parse 相同的掩码形式
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (width, height) = self.size();
+        for y in 0..height {
+            if y > 0 {
+                write!(f, "/")?;
+            }
+            for x in 0..width {
+                let occupied = self.mask & (1 << (y * width + x)) != 0;
+                write!(f, "{}", if occupied { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 每种棋子的形状
+///
+/// 每格用 3 位编码, 空格和 4 种内置棋子之外只剩 3 个编码, 所以最多定义 3 种自定义形状.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Shapes {
+    custom: [Option<(char, Shape)>; 3],
+}
+
+/// 自定义形状的第一个格子编码
+const CUSTOM_CODE: u8 = 5;
+
+impl Shapes {
+    /// 定义一种新形状, 返回它的棋子类型
+    pub fn define(&mut self, letter: char, shape: Shape) -> anyhow::Result<BlockType> {
+        anyhow::ensure!(
+            letter.is_alphabetic() && !"chvpx".contains(letter),
+            "letter {} is reserved",
+            letter
+        );
+        anyhow::ensure!(self.ty(letter).is_none(), "shape {} defined twice", letter);
+        let i = self
+            .custom
+            .iter()
+            .position(|e| e.is_none())
+            .ok_or_else(|| anyhow::anyhow!("at most {} custom shapes", self.custom.len()))?;
+        self.custom[i] = Some((letter, shape));
+        Ok(BlockType::Custom(CUSTOM_CODE + i as u8))
+    }
+
+    /// 是否没有自定义形状
+    pub fn is_empty(&self) -> bool {
+        self.custom.iter().all(|e| e.is_none())
+    }
+
+    /// 所有形状是否都左右对称, 此时互为镜像的局面可以合并
+    pub fn is_symmetric(&self) -> bool {
+        self.iter().all(|e| e.2.is_symmetric())
+    }
+
+    /// 所有自定义形状
+    pub fn iter(&self) -> impl Iterator<Item = (BlockType, char, Shape)> + '_ {
+        self.custom.iter().enumerate().filter_map(|(i, e)| {
+            e.map(|(letter, shape)| (BlockType::Custom(CUSTOM_CODE + i as u8), letter, shape))
+        })
+    }
+
+    /// 字母对应的棋子类型
+    pub fn ty(&self, letter: char) -> Option<BlockType> {
+        [
+            BlockType::CaoCao,
+            BlockType::Horizontal,
+            BlockType::Vertical,
+            BlockType::Pawn,
+        ]
+        .into_iter()
+        .find(|e| e.letter() == Some(letter))
+        .or_else(|| self.iter().find(|e| e.1 == letter).map(|e| e.0))
+    }
+
+    /// 棋子类型的字母
+    pub fn letter(&self, ty: BlockType) -> Option<char> {
+        ty.letter()
+            .or_else(|| self.iter().find(|e| e.0 == ty).map(|e| e.1))
+    }
+
+    /// 棋子类型是否有形状
+    pub fn contains(&self, ty: BlockType) -> bool {
+        self.get(ty).is_some()
+    }
+
+    /// 棋子类型的形状, 未定义的自定义类型视为一格
+    pub fn shape(&self, ty: BlockType) -> Shape {
+        self.get(ty).unwrap_or(Shape::rect(1, 1))
+    }
+
+    fn get(&self, ty: BlockType) -> Option<Shape> {
+        match ty {
+            BlockType::CaoCao => Some(Shape::rect(2, 2)),
+            BlockType::Horizontal => Some(Shape::rect(2, 1)),
+            BlockType::Vertical => Some(Shape::rect(1, 2)),
+            BlockType::Pawn => Some(Shape::rect(1, 1)),
+            BlockType::Custom(code) => self
+                .custom
+                .get(code.checked_sub(CUSTOM_CODE)? as usize)
+                .copied()
+                .flatten()
+                .map(|e| e.1),
+        }
+    }
+}
+
+const CELL_BITS: usize = 3;
+const CELL_MASK: u128 = (1 << CELL_BITS) - 1;
+//宽高各占 4 位, 放在格子之后的最高 8 位
+const SIZE_SHIFT: usize = MAX_CELLS * CELL_BITS;
+pub(crate) const SIZE_MASK: u128 = !((1 << SIZE_SHIFT) - 1);
+//局面码使用的 base64url 字符
+const CODE_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// 一个局面, 每格用 3 位记录该位置上的棋子类型, 按行优先打包进 u128, 最高 8 位记录棋盘宽高
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeValue(u128);
+
+impl Default for NodeValue {
+    /// 经典 4x5 的空棋盘
+    fn default() -> Self {
+        Self(Self::size_bits(WIDTH, HEIGHT))
+    }
+}
+
+impl NodeValue {
+    /// width x height 的空棋盘
+    pub fn new(width: usize, height: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            (2..=MAX_SIDE).contains(&width)
+                && (2..=MAX_SIDE).contains(&height)
+                && width * height <= MAX_CELLS,
+            "unsupported board size {}x{}",
+            width,
+            height
+        );
+        Ok(Self(Self::size_bits(width, height)))
+    }
+
+    pub fn width(&self) -> usize {
+        ((self.0 >> SIZE_SHIFT) & 0xf) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.0 >> (SIZE_SHIFT + 4)) as usize
+    }
+
+    /// 曹操到达出口时左上角所在的列: 底部正中, 宽为奇数时中间偏左和偏右都算, 使终局左右对称
+    pub(crate) fn exit_columns(&self) -> std::ops::RangeInclusive<usize> {
+        (self.width() - 2) / 2..=(self.width() - 1) / 2
+    }
+
+    /// 曹操是否已经到达出口
+    pub fn is_finish(&self) -> bool {
+        let y = self.height() - 1;
+        self.exit_columns().any(|x| {
+            self.get(x, y) == Some(BlockType::CaoCao)
+                && self.get(x + 1, y) == Some(BlockType::CaoCao)
+        })
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, val: Option<BlockType>) {
+        let shift = self.shift(x, y);
+        self.0 = (self.0 & !(CELL_MASK << shift)) | ((BlockType::code(val) as u128) << shift);
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<BlockType> {
+        BlockType::from_code(((self.0 >> self.shift(x, y)) & CELL_MASK) as u64)
+    }
+
+    /// 左右镜像后的局面
+    pub fn mirror(&self) -> Self {
+        let mut ret = Self(self.0 >> SIZE_SHIFT << SIZE_SHIFT);
+        let width = self.width();
+        for y in 0..self.height() {
+            for x in 0..width {
+                ret.set(width - 1 - x, y, self.get(x, y));
+            }
+        }
+        ret
+    }
+
+    /// 局面与其镜像中编码较小的一个, 互为镜像的局面到终局的步数相同, 搜索时只需记录一个
+    pub fn canonical(&self) -> Self {
+        (*self).min(self.mirror())
+    }
+
+    /// (x, y) 格在编码中占据的位
+    pub(crate) fn cell_mask(&self, x: usize, y: usize) -> u128 {
+        CELL_MASK << self.shift(x, y)
+    }
+
+    /// 打包后的整数编码, 包括宽高
+    pub fn bits(&self) -> u128 {
+        self.0
+    }
+
+    /// 从整数编码还原, 检查宽高和格子之外的位是否为 0
+    pub fn from_bits(bits: u128) -> anyhow::Result<Self> {
+        let (width, height) = Self(bits).size();
+        Self::new(width, height)?;
+        anyhow::ensure!(
+            (bits & ((1 << SIZE_SHIFT) - 1)) >> (width * height * CELL_BITS) == 0,
+            "invalid board bits {:#x}",
+            bits
+        );
+        Ok(Self(bits))
+    }
+
+    /// 单行的局面码, 可以直接放进网址
+    ///
+    /// 前两个字符是十六进制的宽和高, 之后按行优先每两格一个 base64url 字符, 低 3 位为前一格.
+    /// 经典 4x5 棋盘为 12 个字符, 同一局面的编码唯一.
+    pub fn encode(&self) -> String {
+        let (width, height) = self.size();
+        let mut ret = format!("{:x}{:x}", width, height);
+        let cells = self.0 & !SIZE_MASK;
+        for i in 0..(width * height).div_ceil(2) {
+            let val = (cells >> (i * 2 * CELL_BITS)) & 0x3f;
+            ret.push(CODE_CHARS[val as usize] as char);
+        }
+        ret
+    }
+
+    /// 从 encode 给出的局面码还原, 只检查编码本身, 棋子是否完整需另外用 validate 检查
+    pub fn decode(code: &str) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("invalid position code {}", code);
+        let mut chars = code.chars();
+        let mut side = || {
+            chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(invalid)
+        };
+        let (width, height) = (side()? as usize, side()? as usize);
+        let mut ret = Self::new(width, height)?;
+
+        let cells = width * height;
+        anyhow::ensure!(
+            chars.clone().count() == cells.div_ceil(2),
+            "position code {} length error, expected {} characters for {}x{}",
+            code,
+            cells.div_ceil(2) + 2,
+            width,
+            height
+        );
+        let mut bits = 0;
+        for (i, c) in chars.enumerate() {
+            let val = CODE_CHARS
+                .iter()
+                .position(|&e| e as char == c)
+                .ok_or_else(invalid)?;
+            bits |= (val as u128) << (i * 2 * CELL_BITS);
+        }
+        //格数为奇数时最后一个字符只用低 3 位
+        anyhow::ensure!(bits >> (cells * CELL_BITS) == 0, invalid());
+        ret.0 |= bits;
+        Ok(ret)
+    }
+
+    /// (宽, 高)
+    pub fn size(&self) -> (usize, usize) {
+        (self.width(), self.height())
+    }
+
+    fn size_bits(width: usize, height: usize) -> u128 {
+        ((width | height << 4) as u128) << SIZE_SHIFT
+    }
+
+    fn shift(&self, x: usize, y: usize) -> usize {
+        debug_assert!(x < self.width() && y < self.height());
+        (y * self.width() + x) * CELL_BITS
+    }
+}
+
+/// 从字符局面解析, 棋盘宽高由行数和每行的字符数决定, 只能使用内置棋子和默认终局, 格式见 README
+pub fn parse_state(state: &str) -> anyhow::Result<NodeValue> {
+    let (ret, rules) = parse_puzzle(state)?;
+    anyhow::ensure!(
+        rules == Rules::default(),
+        "custom shapes and goals need parse_puzzle"
+    );
+    Ok(ret)
+}
+
+/// 解析局面和其中定义的自定义形状与终局, 计步方式为默认值
+///
+/// "字母=掩码" 的行定义自定义形状, "goal c 1,3" 的行指定目标棋子,
+/// 单独的 "goal" 行之后的各行是终局图案, 其余各行是局面.
+pub fn parse_puzzle(state: &str) -> anyhow::Result<(NodeValue, Rules)> {
+    let (ret, rules, _) = parse_named_puzzle(state)?;
+    Ok((ret, rules))
+}
+
+/// 与 parse_puzzle 相同, 另外记录单独标出的棋子
+///
+/// 类型字母之外的字母各表示一个棋子, 类型由它占据的形状决定, "字母:名字" 的行给棋子起名字,
+/// 只有一个棋子的类型字母 (如 c) 也可以起名字.
+pub fn parse_named_puzzle(state: &str) -> anyhow::Result<(NodeValue, Rules, Roster)> {
+    let mut shapes = Shapes::default();
+    let mut lines = Vec::new();
+    let mut names = Vec::new();
+    let mut piece = None;
+    let mut pattern: Option<Vec<&str>> = None;
+    for line in state.lines().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        if line == "goal" || line.starts_with("goal ") {
+            anyhow::ensure!(piece.is_none() && pattern.is_none(), "goal defined twice");
+            match line["goal".len()..].trim() {
+                "" => pattern = Some(Vec::new()),
+                e => piece = Some(e),
+            }
+            continue;
+        }
+        if let Some((letter, name)) = line.split_once(':') {
+            let c = single_letter(letter)?;
+            anyhow::ensure!(
+                names.iter().all(|e: &(char, &str)| e.0 != c),
+                "piece {} named twice",
+                c
+            );
+            names.push((c, name.trim()));
+            continue;
+        }
+        match line.split_once('=') {
+            Some((letter, mask)) => {
+                shapes.define(single_letter(letter)?, Shape::parse(mask)?)?;
+            }
+            None => match pattern.as_mut() {
+                Some(pattern) => pattern.push(line),
+                None => lines.push(line),
+            },
+        }
+    }
+
+    let width = lines.first().map_or(0, |e| e.chars().count());
+    let mut blocks = NodeValue::new(width, lines.len())?;
+    //局面中的问题全部收集后一起返回
+    let mut problems = Vec::new();
+    //单独标出的字母和它占据的格子, 按第一次出现的顺序
+    let mut letters: Vec<(char, Vec<(usize, usize)>)> = Vec::new();
+    for (y, line) in lines.iter().enumerate() {
+        let count = line.chars().count();
+        if count != width {
+            problems.push(Problem::at(
+                count.min(width),
+                y,
+                format!("row {} has {} cells, expected {}", y, count, width),
+            ));
+        }
+        for (x, c) in line.chars().take(width).enumerate() {
+            let named = names.iter().any(|e| e.0 == c);
+            let val = match c {
+                'x' => None,
+                _ => shapes.ty(c),
+            };
+            if named || (c != 'x' && val.is_none()) {
+                if !c.is_alphabetic() {
+                    problems.push(Problem::at(
+                        x,
+                        y,
+                        format!("unknown token {} at ({},{})", c, x, y),
+                    ));
+                    continue;
+                }
+                match letters.iter_mut().find(|e| e.0 == c) {
+                    Some(e) => e.1.push((x, y)),
+                    None => letters.push((c, vec![(x, y)])),
+                }
+            }
+            blocks.set(x, y, val);
+        }
+    }
+
+    let mut roster = Roster::default();
+    for (letter, cells) in letters {
+        let ((x, y), shape) = piece_shape(&cells);
+        let ty = match (shapes.ty(letter), shape) {
+            (Some(ty), Some(shape)) if shapes.shape(ty) == shape => Some(ty),
+            (None, Some(shape)) => [
+                BlockType::CaoCao,
+                BlockType::Horizontal,
+                BlockType::Vertical,
+                BlockType::Pawn,
+            ]
+            .into_iter()
+            .chain(shapes.iter().map(|e| e.0))
+            .find(|e| shapes.shape(*e) == shape),
+            _ => None,
+        };
+        let Some(ty) = ty else {
+            let message = match shape {
+                Some(_) if shapes.ty(letter).is_none() => {
+                    format!("{} at ({},{}) matches no piece shape", letter, x, y)
+                }
+                _ => format!("{} at ({},{}) is not a single piece", letter, x, y),
+            };
+            problems.push(Problem::at(x, y, message));
+            continue;
+        };
+        for &(x, y) in cells.iter() {
+            blocks.set(x, y, Some(ty));
+        }
+        roster.pieces.push(NamedPiece {
+            letter,
+            name: names
+                .iter()
+                .find(|e| e.0 == letter)
+                .map(|e| e.1.to_string()),
+            block: Block { ty, x, y },
+        });
+    }
+    for e in names.iter() {
+        if !lines.iter().any(|line| line.contains(e.0)) {
+            problems.push(Problem::new(format!("named piece {} not on board", e.0)));
+        }
+    }
+
+    //终局条件决定是否必须有曹操, 先于棋子检查
+    let goal = match (piece, pattern) {
+        (Some(e), _) => Goal::parse_piece(e, &shapes, blocks.size())?,
+        (_, Some(e)) => Goal::Pattern(Pattern::parse(&e, &shapes, blocks.size())?),
+        _ => Goal::Exit,
+    };
+    //无法识别的格子按空格继续检查棋子
+    let rules = Rules {
+        shapes,
+        goal,
+        ..Default::default()
+    };
+    check_blocks(&blocks, &rules, &mut problems);
+    if !problems.is_empty() {
+        return Err(ValidationError { problems }.into());
+    }
+    Ok((blocks, rules, roster))
+}
+
+fn single_letter(text: &str) -> anyhow::Result<char> {
+    let mut chars = text.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => anyhow::bail!("invalid letter {}", text),
+    }
+}
+
+/// 一个字母占据的格子的外接矩形左上角和组成的形状, 不连通时形状为 None
+fn piece_shape(cells: &[(usize, usize)]) -> ((usize, usize), Option<Shape>) {
+    let x = cells.iter().map(|e| e.0).min().unwrap_or(0);
+    let y = cells.iter().map(|e| e.1).min().unwrap_or(0);
+    let width = cells.iter().map(|e| e.0 - x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|e| e.1 - y + 1).max().unwrap_or(0);
+    let mask: Vec<String> = (0..height)
+        .map(|dy| {
+            (0..width)
+                .map(|dx| {
+                    if cells.contains(&(x + dx, y + dy)) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    ((x, y), Shape::parse(&mask.join("/")).ok())
+}
@@ -0,0 +1,667 @@
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use crate::board::{Block, BlockType, MAX_CELLS, NodeValue, Shape, Shapes};
+use crate::goal::Goal;
+use crate::notation::{MoveFormatter, Notation};
+use crate::validate::{ValidationError, check_blocks};
+
+/// 移动方向
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// 单位位移 (dx, dy)
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    /// (x, y) 沿该方向移动一格, 越过左边或上边时为 None
+    pub(crate) fn step(self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        let (dx, dy) = self.delta();
+        Some((
+            x.checked_add_signed(dx as isize)?,
+            y.checked_add_signed(dy as isize)?,
+        ))
+    }
+}
+
+/// 计算步数的方式
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Metric {
+    /// 每移动一格算一步
+    Cell,
+    /// 沿直线滑动任意格算一步
+    #[default]
+    Slide,
+    /// 同一个棋子连续移动算一步, 可以拐弯, 如小兵经过两个空格的 L 形移动
+    Piece,
+}
+
+impl FromStr for Metric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "cell" => Ok(Metric::Cell),
+            "slide" => Ok(Metric::Slide),
+            "piece" => Ok(Metric::Piece),
+            _ => anyhow::bail!("unknown metric {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    /// 与 from_str 相同的名字
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Metric::Cell => "cell",
+            Metric::Slide => "slide",
+            Metric::Piece => "piece",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 一步移动中棋子依次经过的方向, 每个方向占 2 位, 最多 64 个
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Path {
+    steps: u128,
+    len: u8,
+}
+
+impl Path {
+    /// 最多能记录的格数, 棋盘最多 40 格, 一步移动不会超过
+    pub const MAX_LEN: usize = 64;
+
+    /// 沿 direction 直线移动 distance 格
+    ///
+    /// distance 超过 MAX_LEN 时 panic.
+    pub fn straight(direction: Direction, distance: usize) -> Self {
+        let mut ret = Self::default();
+        for _ in 0..distance {
+            ret.push(direction);
+        }
+        ret
+    }
+
+    /// 再沿 direction 移动一格, 已有 MAX_LEN 格时 panic
+    pub fn push(&mut self, direction: Direction) {
+        assert!(
+            self.len() < Self::MAX_LEN,
+            "path longer than {}",
+            Self::MAX_LEN
+        );
+        let code = Direction::ALL.iter().position(|e| *e == direction).unwrap() as u128;
+        self.steps |= code << (self.len * 2);
+        self.len += 1;
+    }
+
+    /// 移动的格数
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Direction> {
+        let steps = self.steps;
+        (0..self.len).map(move |i| Direction::ALL[((steps >> (i * 2)) & 3) as usize])
+    }
+
+    /// 是否只沿一个方向移动
+    pub fn is_straight(&self) -> bool {
+        self.iter().all(|e| Some(e) == self.iter().next())
+    }
+}
+
+/// 一步移动: 左上角位于 from 的 piece 沿 path 移动
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Move {
+    pub piece: BlockType,
+    pub from: (usize, usize),
+    pub path: Path,
+}
+
+impl Move {
+    /// 移动后棋子左上角的位置
+    pub fn to(&self) -> (usize, usize) {
+        let (mut x, mut y) = (self.from.0 as i32, self.from.1 as i32);
+        for e in self.path.iter() {
+            let (dx, dy) = e.delta();
+            x += dx;
+            y += dy;
+        }
+        (x as usize, y as usize)
+    }
+}
+
+impl fmt::Display for Move {
+    /// 默认写法, 如 "(1,3) 下", 其他写法见 Notation
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Notation::default().format(self))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Notation::default().path(self))
+    }
+}
+
+/// 求解规则: 计步方式, 棋子形状和终局条件
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rules {
+    pub metric: Metric,
+    pub shapes: Shapes,
+    pub goal: Goal,
+}
+
+impl Rules {
+    /// 局面是否满足终局条件
+    pub fn is_finish(&self, state: &NodeValue) -> bool {
+        match self.goal {
+            Goal::Exit => state.is_finish(),
+            Goal::Piece { ty, x, y } => {
+                //先比较格子, 相符时再确认是一个完整的棋子而不是相邻两个棋子的各一部分
+                self.shapes
+                    .shape(ty)
+                    .place((x, y), state.size())
+                    .is_some_and(|mut cells| cells.all(|(x, y)| state.get(x, y) == Some(ty)))
+                    && Game::new_unchecked(state, self)
+                        .blocks
+                        .contains(&Block { ty, x, y })
+            }
+            Goal::Pattern(e) => e.matches(state),
+        }
+    }
+
+    /// 去重用的键, 形状和终局条件都左右对称时互为镜像的局面使用同一个键
+    pub(crate) fn key(&self, state: &NodeValue) -> NodeValue {
+        if self.shapes.is_symmetric() && self.goal.is_symmetric(&self.shapes, state.width()) {
+            state.canonical()
+        } else {
+            *state
+        }
+    }
+}
+
+pub(crate) struct Game<'a> {
+    pub(crate) blocks: Vec<Block>,
+
+    pub(crate) state: NodeValue,
+    pub(crate) empty_cell: Vec<(usize, usize)>,
+    rules: &'a Rules,
+}
+
+impl<'a> Game<'a> {
+    pub(crate) fn new_unchecked(state: &NodeValue, rules: &'a Rules) -> Self {
+        let size = state.size();
+        let width = size.0;
+        let mut blocks = Vec::with_capacity(10);
+        let mut visited = [false; MAX_CELLS];
+        let mut empty_cell = Vec::with_capacity(2);
+
+        for y in 0..size.1 {
+            for x in 0..width {
+                if visited[y * width + x] {
+                    continue;
+                }
+                match state.get(x, y) {
+                    Some(ty) => {
+                        //行优先第一个未访问的格子就是棋子形状的第一格
+                        let shape = rules.shapes.shape(ty);
+                        let (fx, fy) = shape.first();
+                        let (x, y) = (x.saturating_sub(fx), y.saturating_sub(fy));
+                        for (x, y) in shape.place((x, y), size).into_iter().flatten() {
+                            visited[y * width + x] = true;
+                        }
+                        blocks.push(Block { ty, x, y });
+                    }
+                    None => empty_cell.push((x, y)),
+                }
+            }
+        }
+
+        Self {
+            blocks,
+            state: *state,
+            empty_cell,
+            rules,
+        }
+    }
+
+    /// 检查局面后解析, 有问题时返回包含所有问题的 ValidationError
+    pub(crate) fn new(state: &NodeValue, rules: &'a Rules) -> anyhow::Result<Self> {
+        let mut problems = Vec::new();
+        let (blocks, empty_cell) = check_blocks(state, rules, &mut problems);
+        if !problems.is_empty() {
+            return Err(ValidationError { problems }.into());
+        }
+
+        Ok(Self {
+            blocks,
+            state: *state,
+            empty_cell,
+            rules,
+        })
+    }
+
+    /// 按规则中的计步方式, 所有合法的一步移动和移动后的局面
+    pub(crate) fn next_nodes(&self, ret: &mut Vec<(Move, NodeValue)>) {
+        match self.rules.metric {
+            Metric::Slide => self.slide_nodes(ret),
+            Metric::Cell => {
+                let start = ret.len();
+                self.slide_nodes(ret);
+                let slides = ret.split_off(start);
+                ret.extend(slides.into_iter().filter(|e| e.0.path.len() == 1));
+            }
+            Metric::Piece => {
+                for e in self.blocks.iter() {
+                    self.path_nodes(e, ret);
+                }
+            }
+        }
+    }
+
+    /// 棋子只经过空格连续移动能到达的所有位置, 按广度优先顺序, 每个位置取最短的路径
+    fn path_nodes(&self, e: &Block, ret: &mut Vec<(Move, NodeValue)>) {
+        let shape = self.rules.shapes.shape(e.ty);
+        let base = remove(&self.state, e, shape);
+        let mut visited = vec![(e.x, e.y)];
+        let mut list = VecDeque::from([((e.x, e.y), Path::default())]);
+        while let Some((pos, path)) = list.pop_front() {
+            for direction in Direction::ALL {
+                let Some(next) = direction.step(pos) else {
+                    continue;
+                };
+                if visited.contains(&next) || !fits(&base, shape, next) {
+                    continue;
+                }
+                visited.push(next);
+                let mut path = path;
+                path.push(direction);
+                let mv = Move {
+                    piece: e.ty,
+                    from: (e.x, e.y),
+                    path,
+                };
+                ret.push((mv, place(&base, e.ty, shape, next)));
+                list.push_back((next, path));
+            }
+        }
+    }
+
+    /// 沿直线滑动的所有移动
+    fn slide_nodes(&self, ret: &mut Vec<(Move, NodeValue)>) {
+        if self.empty_cell.len() == 2
+            && self
+                .blocks
+                .iter()
+                .all(|e| !matches!(e.ty, BlockType::Custom(_)))
+        {
+            self.two_empty_slide_nodes(ret);
+        } else {
+            self.generic_slide_nodes(ret);
+        }
+    }
+
+    /// 任意空格数时沿直线滑动的所有移动, 每个方向从一格开始逐格增加
+    pub(crate) fn generic_slide_nodes(&self, ret: &mut Vec<(Move, NodeValue)>) {
+        for e in self.blocks.iter() {
+            let shape = self.rules.shapes.shape(e.ty);
+            let base = remove(&self.state, e, shape);
+            for direction in Direction::ALL {
+                let mut pos = (e.x, e.y);
+                let mut path = Path::default();
+                while let Some(next) = direction.step(pos)
+                    && fits(&base, shape, next)
+                {
+                    pos = next;
+                    path.push(direction);
+                    let mv = Move {
+                        piece: e.ty,
+                        from: (e.x, e.y),
+                        path,
+                    };
+                    ret.push((mv, place(&base, e.ty, shape, pos)));
+                }
+            }
+        }
+    }
+
+    /// 恰好两个空格且只有内置棋子 (如经典的 10 个棋子加 2 个空格) 时直接比较空格位置, 最多滑动两格, 结果与 generic_slide_nodes 相同
+    fn two_empty_slide_nodes(&self, ret: &mut Vec<(Move, NodeValue)>) {
+        let (width, height) = self.state.size();
+        for e in self.blocks.iter() {
+            let x = e.x;
+            let y = e.y;
+            let mv = |direction, distance| Move {
+                piece: e.ty,
+                from: (x, y),
+                path: Path::straight(direction, distance),
+            };
+            match e.ty {
+                BlockType::CaoCao => {
+                    //上移
+                    if y >= 1 && self.empty_cell == [(x, y - 1), (x + 1, y - 1)] {
+                        let mut node = self.state;
+                        node.set(x, y - 1, Some(e.ty));
+                        node.set(x + 1, y - 1, Some(e.ty));
+                        node.set(x, y + 1, None);
+                        node.set(x + 1, y + 1, None);
+                        ret.push((mv(Direction::Up, 1), node));
+                    }
+
+                    //下移
+                    if y + 2 < height && self.empty_cell == [(x, y + 2), (x + 1, y + 2)] {
+                        let mut node = self.state;
+                        node.set(x, y + 2, Some(e.ty));
+                        node.set(x + 1, y + 2, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Down, 1), node));
+                    }
+
+                    //左移
+                    if x >= 1 && self.empty_cell == [(x - 1, y), (x - 1, y + 1)] {
+                        let mut node = self.state;
+                        node.set(x - 1, y, Some(e.ty));
+                        node.set(x - 1, y + 1, Some(e.ty));
+                        node.set(x + 1, y, None);
+                        node.set(x + 1, y + 1, None);
+                        ret.push((mv(Direction::Left, 1), node));
+                    }
+
+                    //右移
+                    if x + 2 < width && self.empty_cell == [(x + 2, y), (x + 2, y + 1)] {
+                        let mut node = self.state;
+                        node.set(x + 2, y, Some(e.ty));
+                        node.set(x + 2, y + 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Right, 1), node));
+                    }
+                }
+                BlockType::Horizontal => {
+                    //上移
+                    if y >= 1 && self.empty_cell == [(x, y - 1), (x + 1, y - 1)] {
+                        let mut node = self.state;
+                        node.set(x, y - 1, Some(e.ty));
+                        node.set(x + 1, y - 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Up, 1), node));
+                    }
+
+                    //下移
+                    if y + 1 < height && self.empty_cell == [(x, y + 1), (x + 1, y + 1)] {
+                        let mut node = self.state;
+                        node.set(x, y + 1, Some(e.ty));
+                        node.set(x + 1, y + 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Down, 1), node));
+                    }
+
+                    //左移一格
+                    if x >= 1 && self.empty_cell.contains(&(x - 1, y)) {
+                        let mut node = self.state;
+                        node.set(x - 1, y, Some(e.ty));
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Left, 1), node));
+                    }
+
+                    //左移二格
+                    if x >= 2 && self.empty_cell == [(x - 2, y), (x - 1, y)] {
+                        let mut node = self.state;
+                        node.set(x - 2, y, Some(e.ty));
+                        node.set(x - 1, y, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Left, 2), node));
+                    }
+
+                    //右移一格
+                    if x + 2 < width && self.empty_cell.contains(&(x + 2, y)) {
+                        let mut node = self.state;
+                        node.set(x + 2, y, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Right, 1), node));
+                    }
+
+                    //右移二格
+                    if x + 3 < width && self.empty_cell == [(x + 2, y), (x + 3, y)] {
+                        let mut node = self.state;
+                        node.set(x + 2, y, Some(e.ty));
+                        node.set(x + 3, y, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x + 1, y, None);
+                        ret.push((mv(Direction::Right, 2), node));
+                    }
+                }
+                BlockType::Vertical => {
+                    //上移一格
+                    if y >= 1 && self.empty_cell.contains(&(x, y - 1)) {
+                        let mut node = self.state;
+                        node.set(x, y - 1, Some(e.ty));
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Up, 1), node));
+                    }
+
+                    //上移二格
+                    if y >= 2 && self.empty_cell == [(x, y - 2), (x, y - 1)] {
+                        let mut node = self.state;
+                        node.set(x, y - 2, Some(e.ty));
+                        node.set(x, y - 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Up, 2), node));
+                    }
+
+                    //下移一格
+                    if y + 2 < height && self.empty_cell.contains(&(x, y + 2)) {
+                        let mut node = self.state;
+                        node.set(x, y + 2, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Down, 1), node));
+                    }
+
+                    //下移二格
+                    if y + 3 < height && self.empty_cell == [(x, y + 2), (x, y + 3)] {
+                        let mut node = self.state;
+                        node.set(x, y + 2, Some(e.ty));
+                        node.set(x, y + 3, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Down, 2), node));
+                    }
+
+                    //左移
+                    if x >= 1 && self.empty_cell == [(x - 1, y), (x - 1, y + 1)] {
+                        let mut node = self.state;
+                        node.set(x - 1, y, Some(e.ty));
+                        node.set(x - 1, y + 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Left, 1), node));
+                    }
+
+                    //右移
+                    if x + 1 < width && self.empty_cell == [(x + 1, y), (x + 1, y + 1)] {
+                        let mut node = self.state;
+                        node.set(x + 1, y, Some(e.ty));
+                        node.set(x + 1, y + 1, Some(e.ty));
+                        node.set(x, y, None);
+                        node.set(x, y + 1, None);
+                        ret.push((mv(Direction::Right, 1), node));
+                    }
+                }
+                BlockType::Pawn => {
+                    //上移一格
+                    if y >= 1 && self.empty_cell.contains(&(x, y - 1)) {
+                        let mut node = self.state;
+                        node.set(x, y - 1, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Up, 1), node));
+                    }
+
+                    //上移二格
+                    if y >= 2 && self.empty_cell == [(x, y - 2), (x, y - 1)] {
+                        let mut node = self.state;
+                        node.set(x, y - 2, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Up, 2), node));
+                    }
+
+                    //下移一格
+                    if y + 1 < height && self.empty_cell.contains(&(x, y + 1)) {
+                        let mut node = self.state;
+                        node.set(x, y + 1, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Down, 1), node));
+                    }
+
+                    //下移二格
+                    if y + 2 < height && self.empty_cell == [(x, y + 1), (x, y + 2)] {
+                        let mut node = self.state;
+                        node.set(x, y + 2, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Down, 2), node));
+                    }
+
+                    //左移一格
+                    if x >= 1 && self.empty_cell.contains(&(x - 1, y)) {
+                        let mut node = self.state;
+                        node.set(x - 1, y, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Left, 1), node));
+                    }
+
+                    //左移二格
+                    if x >= 2 && self.empty_cell == [(x - 2, y), (x - 1, y)] {
+                        let mut node = self.state;
+                        node.set(x - 2, y, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Left, 2), node));
+                    }
+
+                    //右移一格
+                    if x + 1 < width && self.empty_cell.contains(&(x + 1, y)) {
+                        let mut node = self.state;
+                        node.set(x + 1, y, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Right, 1), node));
+                    }
+
+                    //右移二格
+                    if x + 2 < width && self.empty_cell == [(x + 1, y), (x + 2, y)] {
+                        let mut node = self.state;
+                        node.set(x + 2, y, Some(e.ty));
+                        node.set(x, y, None);
+                        ret.push((mv(Direction::Right, 2), node));
+                    }
+                }
+                //有自定义形状时不走这里
+                BlockType::Custom(_) => {}
+            }
+        }
+    }
+
+    /// 按规则中的计步方式, 从当前局面到 next 的一步移动
+    pub(crate) fn move_between(&self, next: &NodeValue) -> anyhow::Result<Move> {
+        let mut next_nodes = Vec::new();
+        self.next_nodes(&mut next_nodes);
+        next_nodes
+            .into_iter()
+            .find(|e| e.1 == *next)
+            .map(|e| e.0)
+            .ok_or_else(|| anyhow::anyhow!("2个状态无法通过一次移动完成转化"))
+    }
+
+    pub(crate) fn move_message(&self, next: &NodeValue) -> anyhow::Result<String> {
+        Ok(self.move_between(next)?.to_string())
+    }
+}
+
+/// 去掉形状为 shape 的棋子 e 后的局面
+fn remove(state: &NodeValue, e: &Block, shape: Shape) -> NodeValue {
+    let mut ret = *state;
+    for (x, y) in shape.place((e.x, e.y), state.size()).into_iter().flatten() {
+        ret.set(x, y, None);
+    }
+    ret
+}
+
+/// 以 (x, y) 为左上角的 shape 是否在棋盘内且只占据空格
+fn fits(state: &NodeValue, shape: Shape, pos: (usize, usize)) -> bool {
+    shape
+        .place(pos, state.size())
+        .is_some_and(|mut cells| cells.all(|(x, y)| state.get(x, y).is_none()))
+}
+
+/// 以 (x, y) 为左上角放入形状为 shape 的 ty
+fn place(state: &NodeValue, ty: BlockType, shape: Shape, pos: (usize, usize)) -> NodeValue {
+    let mut ret = *state;
+    for (x, y) in shape.place(pos, state.size()).into_iter().flatten() {
+        ret.set(x, y, Some(ty));
+    }
+    ret
+}
+
+impl NodeValue {
+    /// 局面上的所有棋子, 按行优先排列, 不检查棋子是否完整, 需要时先用 validate 检查
+    pub fn blocks(&self, rules: &Rules) -> Vec<Block> {
+        Game::new_unchecked(self, rules).blocks
+    }
+
+    /// 按 rules 所有合法的一步移动和移动后的局面
+    pub fn moves(&self, rules: &Rules) -> Vec<(Move, NodeValue)> {
+        let mut ret = Vec::new();
+        Game::new_unchecked(self, rules).next_nodes(&mut ret);
+        ret
+    }
+
+    /// 执行一步移动, 路径上每一格都必须为空, 否则返回错误
+    pub fn apply(&self, mv: &Move, rules: &Rules) -> anyhow::Result<NodeValue> {
+        let illegal = || anyhow::anyhow!("illegal move {}", mv);
+        let block = Block {
+            ty: mv.piece,
+            x: mv.from.0,
+            y: mv.from.1,
+        };
+        if mv.path.is_empty() || !Game::new_unchecked(self, rules).blocks.contains(&block) {
+            return Err(illegal());
+        }
+
+        let shape = rules.shapes.shape(mv.piece);
+        let base = remove(self, &block, shape);
+        let mut pos = mv.from;
+        for e in mv.path.iter() {
+            pos = e.step(pos).ok_or_else(illegal)?;
+            if !fits(&base, shape, pos) {
+                return Err(illegal());
+            }
+        }
+        Ok(place(&base, mv.piece, shape, pos))
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+
+use crate::board::{Block, BlockType, NodeValue};
+use crate::game::Rules;
+use crate::goal::Goal;
+use crate::solve::Solution;
+
+/// GIF 动画的设置
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GifOptions {
+    /// 每格的边长, 像素
+    pub cell: u16,
+    /// 每个局面停留的时间, 单位 1/100 秒
+    pub delay: u16,
+    /// 棋子每移动一格插入的过渡帧数, 为 0 时直接跳到下一个局面
+    pub tweens: u16,
+}
+
+impl GifOptions {
+    /// cell 的上限, 最大的棋盘每帧也只有几 MB
+    pub const MAX_CELL: u16 = 160;
+    /// tweens 的上限
+    pub const MAX_TWEENS: u16 = 30;
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            cell: 40,
+            delay: 50,
+            tweens: 4,
+        }
+    }
+}
+
+//过渡帧停留的时间, 小于 2 时很多浏览器会按 10 处理
+const TWEEN_DELAY: u16 = 3;
+
+//调色板: 背景, 边框, 棋盘, 之后是各种棋子, 共 16 色
+const PALETTE: [[u8; 3]; 16] = [
+    [255, 255, 255],
+    [107, 79, 58],
+    [243, 234, 216],
+    [217, 83, 79],
+    [66, 139, 202],
+    [92, 184, 92],
+    [240, 173, 78],
+    [155, 89, 182],
+    [26, 188, 156],
+    [160, 120, 90],
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+];
+const FRAME: u8 = 1;
+const BOARD: u8 = 2;
+
+/// 棋子类型在调色板中的序号
+fn color(ty: BlockType) -> u8 {
+    match ty {
+        BlockType::CaoCao => 3,
+        BlockType::Horizontal => 4,
+        BlockType::Vertical => 5,
+        BlockType::Pawn => 6,
+        BlockType::Custom(code) => 2 + code,
+    }
+}
+
+/// 把解画成循环播放的 GIF 动画, 不依赖外部程序
+///
+/// 每个局面停留 delay, 移动的棋子按 tweens 插入过渡帧平滑滑过去, 终局停留 4 倍的时间.
+pub fn gif(solution: &Solution, options: &GifOptions) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(
+        options.cell <= GifOptions::MAX_CELL,
+        "cell {} exceeds {}",
+        options.cell,
+        GifOptions::MAX_CELL
+    );
+    anyhow::ensure!(
+        options.tweens <= GifOptions::MAX_TWEENS,
+        "tweens {} exceeds {}",
+        options.tweens,
+        GifOptions::MAX_TWEENS
+    );
+    //分配像素之前检查图片大小
+    let (width, height) = Painter::size(&solution.start, options);
+    anyhow::ensure!(
+        width <= u16::MAX as usize && height <= u16::MAX as usize,
+        "image {}x{} too large for gif",
+        width,
+        height
+    );
+    let positions = solution.positions()?;
+    let painter = Painter::new(&solution.start, &solution.rules, options);
+    //每帧画好后立即编码
+    let mut encoder = Encoder::new(width, height);
+    encoder.frame(painter.board(&positions[0], None), options.delay);
+    for (i, mv) in solution.moves.iter().enumerate() {
+        let block = Block {
+            ty: mv.piece,
+            x: mv.from.0,
+            y: mv.from.1,
+        };
+        //沿路径经过的每格的左上角
+        let mut points = vec![(mv.from.0 as f64, mv.from.1 as f64)];
+        for e in mv.path.iter() {
+            let (dx, dy) = e.delta();
+            let last = points[points.len() - 1];
+            points.push((last.0 + dx as f64, last.1 + dy as f64));
+        }
+        let tweens = options.tweens as usize;
+        for k in 1..mv.path.len() * tweens {
+            let (seg, frac) = (k / tweens, (k % tweens) as f64 / tweens as f64);
+            let (from, to) = (points[seg], points[seg + 1]);
+            let pos = (
+                from.0 + (to.0 - from.0) * frac,
+                from.1 + (to.1 - from.1) * frac,
+            );
+            encoder.frame(
+                painter.board(&positions[i], Some((block, pos))),
+                TWEEN_DELAY,
+            );
+        }
+        let last = i + 1 == solution.len();
+        let delay = if last {
+            options.delay.saturating_mul(4)
+        } else {
+            options.delay
+        };
+        encoder.frame(painter.board(&positions[i + 1], None), delay);
+    }
+    Ok(encoder.finish())
+}
+
+/// 把局面画成调色板序号组成的像素
+struct Painter<'a> {
+    rules: &'a Rules,
+    cell: usize,
+    pad: usize,
+    width: usize,
+    height: usize,
+    //没有棋子的棋盘
+    empty: Vec<u8>,
+}
+
+impl<'a> Painter<'a> {
+    /// 包括边框的图片大小
+    fn size(state: &NodeValue, options: &GifOptions) -> (usize, usize) {
+        let cell = options.cell.max(4) as usize;
+        let pad = cell / 4;
+        let (w, h) = state.size();
+        (w * cell + pad * 2, h * cell + pad * 2)
+    }
+
+    fn new(state: &NodeValue, rules: &'a Rules, options: &GifOptions) -> Self {
+        let cell = options.cell.max(4) as usize;
+        let pad = cell / 4;
+        let (w, h) = state.size();
+        let (width, height) = Self::size(state, options);
+        let mut ret = Self {
+            rules,
+            cell,
+            pad,
+            width,
+            height,
+            empty: vec![FRAME; width * height],
+        };
+        let mut empty = std::mem::take(&mut ret.empty);
+        ret.fill(&mut empty, (pad, pad), (w * cell, h * cell), BOARD);
+        //出口处的边框留空
+        if rules.goal == Goal::Exit {
+            let columns = state.exit_columns();
+            let x = pad + columns.start() * cell;
+            let len = (columns.end() + 2 - columns.start()) * cell;
+            ret.fill(&mut empty, (x, pad + h * cell), (len, pad), BOARD);
+        }
+        ret.empty = empty;
+        ret
+    }
+
+    /// 画出局面, moving 为正在移动的棋子和它左上角当前所在的格子位置, 可以是小数
+    fn board(&self, state: &NodeValue, moving: Option<(Block, (f64, f64))>) -> Vec<u8> {
+        let mut ret = self.empty.clone();
+        for e in state.blocks(self.rules) {
+            if moving.is_some_and(|m| m.0 == e) {
+                continue;
+            }
+            let origin = (self.pad + e.x * self.cell, self.pad + e.y * self.cell);
+            self.piece(&mut ret, e.ty, origin);
+        }
+        if let Some((e, (x, y))) = moving {
+            let origin = (
+                self.pad + (x * self.cell as f64).round() as usize,
+                self.pad + (y * self.cell as f64).round() as usize,
+            );
+            self.piece(&mut ret, e.ty, origin);
+        }
+        ret
+    }
+
+    /// 以 origin 像素为外接矩形左上角画出棋子, 同一棋子相邻的格子之间不留缝
+    fn piece(&self, pixels: &mut [u8], ty: BlockType, origin: (usize, usize)) {
+        let shape = self.rules.shapes.shape(ty);
+        let inset = (self.cell / 16).max(1);
+        let contains = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) => shape.cells().any(|e| e == (x, y)),
+            _ => false,
+        };
+        for (x, y) in shape.cells() {
+            let left = if contains(x.checked_sub(1), Some(y)) {
+                0
+            } else {
+                inset
+            };
+            let right = if contains(Some(x + 1), Some(y)) {
+                0
+            } else {
+                inset
+            };
+            let top = if contains(Some(x), y.checked_sub(1)) {
+                0
+            } else {
+                inset
+            };
+            let bottom = if contains(Some(x), Some(y + 1)) {
+                0
+            } else {
+                inset
+            };
+            self.fill(
+                pixels,
+                (
+                    origin.0 + x * self.cell + left,
+                    origin.1 + y * self.cell + top,
+                ),
+                (self.cell - left - right, self.cell - top - bottom),
+                color(ty),
+            );
+        }
+    }
+
+    fn fill(&self, pixels: &mut [u8], (x, y): (usize, usize), (w, h): (usize, usize), color: u8) {
+        for row in y..(y + h).min(self.height) {
+            let start = row * self.width;
+            pixels[start + x.min(self.width)..start + (x + w).min(self.width)].fill(color);
+        }
+    }
+}
+
+/// GIF89a 编码器, 每帧只写入与上一帧不同的矩形区域, 只保留上一帧的像素
+struct Encoder {
+    out: Vec<u8>,
+    width: usize,
+    height: usize,
+    previous: Option<Vec<u8>>,
+}
+
+impl Encoder {
+    fn new(width: usize, height: usize) -> Self {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"GIF89a");
+        push_u16(&mut out, width);
+        push_u16(&mut out, height);
+        //全局调色板, 8 位色深, 16 色
+        out.extend_from_slice(&[0xf3, 0, 0]);
+        out.extend(PALETTE.iter().flatten());
+        //无限循环
+        out.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+        Self {
+            out,
+            width,
+            height,
+            previous: None,
+        }
+    }
+
+    fn frame(&mut self, pixels: Vec<u8>, delay: u16) {
+        let (width, height) = (self.width, self.height);
+        let (x, y, w, h) = match self.previous.as_ref() {
+            Some(previous) => changed(width, height, previous, &pixels),
+            None => (0, 0, width, height),
+        };
+        let out = &mut self.out;
+
+        //图形控制扩展: 不清除上一帧
+        out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x04]);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        //图像描述
+        out.push(0x2c);
+        for e in [x, y, w, h] {
+            push_u16(out, e);
+        }
+        out.push(0);
+
+        let mut data = Vec::with_capacity(w * h);
+        for row in y..y + h {
+            data.extend_from_slice(&pixels[row * width + x..row * width + x + w]);
+        }
+        out.push(MIN_CODE_SIZE);
+        for e in lzw(&data).chunks(255) {
+            out.push(e.len() as u8);
+            out.extend_from_slice(e);
+        }
+        out.push(0);
+        self.previous = Some(pixels);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+fn push_u16(out: &mut Vec<u8>, val: usize) {
+    out.extend_from_slice(&(val as u16).to_le_bytes());
+}
+
+/// 两帧之间不同的像素所在的矩形 (x, y, 宽, 高), 完全相同时为左上角一个像素
+fn changed(width: usize, height: usize, a: &[u8], b: &[u8]) -> (usize, usize, usize, usize) {
+    let (mut x0, mut y0, mut x1, mut y1) = (width, height, 0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if a[y * width + x] != b[y * width + x] {
+                (x0, y0) = (x0.min(x), y0.min(y));
+                (x1, y1) = (x1.max(x + 1), y1.max(y + 1));
+            }
+        }
+    }
+    if x0 >= x1 {
+        (0, 0, 1, 1)
+    } else {
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+//16 色调色板的 LZW 最小码长
+const MIN_CODE_SIZE: u8 = 4;
+const MAX_CODE: u16 = 4096;
+
+/// GIF 的变长 LZW 压缩, 码表满时输出清除码重新开始
+pub(crate) fn lzw(data: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = clear + 2;
+    let mut size = MIN_CODE_SIZE as u32 + 1;
+    writer.write(clear, size);
+
+    let mut prefix = None;
+    for &e in data {
+        let Some(p) = prefix else {
+            prefix = Some(e as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(p, e)) {
+            prefix = Some(code);
+            continue;
+        }
+        writer.write(p, size);
+        //解码端在码表增长到当前码长的上限时加宽
+        if next >= 1 << size && size < 12 {
+            size += 1;
+        }
+        if next < MAX_CODE {
+            table.insert((p, e), next);
+            next += 1;
+        } else {
+            writer.write(clear, size);
+            table.clear();
+            next = clear + 2;
+            size = MIN_CODE_SIZE as u32 + 1;
+        }
+        prefix = Some(e as u16);
+    }
+    if let Some(p) = prefix {
+        writer.write(p, size);
+        if next >= 1 << size && size < 12 {
+            size += 1;
+        }
+    }
+    writer.write(clear + 1, size);
+    writer.finish()
+}
+
+/// 低位在前写入变长的码
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
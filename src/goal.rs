@@ -0,0 +1,186 @@
+use crate::board::{BlockType, NodeValue, SIZE_MASK, Shapes};
+
+/// 终局条件
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Goal {
+    /// 曹操到达底部出口
+    #[default]
+    Exit,
+    /// 某种棋子中的一个以外接矩形左上角到达 (x, y)
+    Piece { ty: BlockType, x: usize, y: usize },
+    /// 与局面图案相符
+    Pattern(Pattern),
+}
+
+impl Goal {
+    /// 从 "c 1,3" 形式解析目标棋子, 字母之后是外接矩形左上角坐标, 可以带括号
+    pub fn parse_piece(text: &str, shapes: &Shapes, size: (usize, usize)) -> anyhow::Result<Self> {
+        let (letter, pos) = text
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("invalid goal {}", text))?;
+        let mut chars = letter.chars();
+        let ty = match (chars.next(), chars.next()) {
+            (Some(c), None) => shapes.ty(c),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("unknown goal piece {}", letter))?;
+
+        let pos = pos.trim().trim_start_matches('(').trim_end_matches(')');
+        let (x, y) = pos
+            .split_once(',')
+            .ok_or_else(|| anyhow::anyhow!("invalid goal position {}", pos))?;
+        Self::piece(ty, (x.trim().parse()?, y.trim().parse()?), shapes, size)
+    }
+
+    /// 外接矩形左上角在 (x, y) 的目标棋子, 超出棋盘时出错
+    pub(crate) fn piece(
+        ty: BlockType,
+        (x, y): (usize, usize),
+        shapes: &Shapes,
+        size: (usize, usize),
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            shapes.shape(ty).place((x, y), size).is_some(),
+            "goal position ({},{}) out of board",
+            x,
+            y
+        );
+        Ok(Self::Piece { ty, x, y })
+    }
+
+    /// 文字形式: "exit", 与 goal 行相同的 "c 1,3", 或者用 / 分隔各行的图案
+    pub fn to_text(&self, shapes: &Shapes) -> String {
+        match *self {
+            Self::Exit => "exit".to_string(),
+            Self::Piece { ty, x, y } => {
+                format!("{} {},{}", shapes.letter(ty).unwrap_or('?'), x, y)
+            }
+            Self::Pattern(e) => e.to_text(shapes),
+        }
+    }
+
+    /// 从 to_text 的文字形式还原
+    pub fn from_text(text: &str, shapes: &Shapes, size: (usize, usize)) -> anyhow::Result<Self> {
+        if text == "exit" {
+            Ok(Self::Exit)
+        } else if text.contains('/') {
+            let rows: Vec<&str> = text.split('/').collect();
+            Ok(Self::Pattern(Pattern::parse(&rows, shapes, size)?))
+        } else {
+            Self::parse_piece(text, shapes, size)
+        }
+    }
+
+    /// 左右镜像后条件是否不变, 此时互为镜像的局面可以合并
+    pub fn is_symmetric(&self, shapes: &Shapes, width: usize) -> bool {
+        match *self {
+            Self::Exit => true,
+            Self::Piece { ty, x, .. } => {
+                let shape = shapes.shape(ty);
+                shape.is_symmetric()
+                    && width
+                        .checked_sub(shape.size().0)
+                        .and_then(|e| e.checked_sub(x))
+                        == Some(x)
+            }
+            Self::Pattern(e) => e.mirror() == e,
+        }
+    }
+}
+
+/// 局面图案, 通配的格子可以是任何棋子或空格
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Pattern {
+    //固定格子的期望值, 通配的格子为空
+    pub(crate) cells: NodeValue,
+    //固定格子和宽高在编码中占据的位
+    pub(crate) mask: u128,
+}
+
+impl Pattern {
+    /// 从与棋盘同样大小的字符行解析, ? 表示通配, 其余字符与局面相同
+    pub fn parse(rows: &[&str], shapes: &Shapes, size: (usize, usize)) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            rows.len() == size.1,
+            "goal pattern has {} rows, expected {}",
+            rows.len(),
+            size.1
+        );
+        let mut ret = Self {
+            cells: NodeValue::new(size.0, size.1)?,
+            mask: SIZE_MASK,
+        };
+        for (y, row) in rows.iter().enumerate() {
+            anyhow::ensure!(
+                row.chars().count() == size.0,
+                "goal pattern size error at row {}, expected {} cells",
+                y,
+                size.0
+            );
+            for (x, c) in row.chars().enumerate() {
+                let val = match c {
+                    '?' => continue,
+                    'x' => None,
+                    _ => Some(
+                        shapes
+                            .ty(c)
+                            .ok_or_else(|| anyhow::anyhow!("unknown token {}", c))?,
+                    ),
+                };
+                ret.cells.set(x, y, val);
+                ret.mask |= ret.cells.cell_mask(x, y);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// 与 parse 相同的字符形式, 各行用 / 分隔
+    pub fn to_text(&self, shapes: &Shapes) -> String {
+        let (width, height) = self.cells.size();
+        let mut ret = String::new();
+        for y in 0..height {
+            if y > 0 {
+                ret.push('/');
+            }
+            for x in 0..width {
+                ret.push(if self.mask & self.cells.cell_mask(x, y) == 0 {
+                    '?'
+                } else {
+                    match self.cells.get(x, y) {
+                        Some(ty) => shapes.letter(ty).unwrap_or('?'),
+                        None => 'x',
+                    }
+                });
+            }
+        }
+        ret
+    }
+
+    /// 局面是否与图案相符
+    pub fn matches(&self, state: &NodeValue) -> bool {
+        state.bits() & self.mask == self.cells.bits()
+    }
+
+    /// (x, y) 为 val 时是否可能相符
+    pub fn allows(&self, x: usize, y: usize, val: Option<BlockType>) -> bool {
+        self.mask & self.cells.cell_mask(x, y) == 0 || self.cells.get(x, y) == val
+    }
+
+    /// 左右镜像后的图案
+    pub fn mirror(&self) -> Self {
+        let mut ret = Self {
+            cells: self.cells.mirror(),
+            mask: SIZE_MASK,
+        };
+        let (width, height) = self.cells.size();
+        for y in 0..height {
+            for x in 0..width {
+                if self.mask & self.cells.cell_mask(x, y) != 0 {
+                    ret.mask |= self.cells.cell_mask(width - 1 - x, y);
+                }
+            }
+        }
+        ret
+    }
+}
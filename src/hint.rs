@@ -0,0 +1,58 @@
+use crate::board::NodeValue;
+use crate::game::{Game, Move, Rules};
+use crate::solve::{Algorithm, Solution, SolveError, solve_with};
+use crate::tablebase::Tablebase;
+
+/// 提示: 到终局的最少步数, 以及能达到该步数的下一步移动
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hint {
+    pub distance: usize,
+    /// 已是终局时为空
+    pub moves: Vec<Move>,
+}
+
+impl From<Solution> for Hint {
+    /// 解的步数和第一步
+    fn from(solution: Solution) -> Self {
+        Self {
+            distance: solution.len(),
+            moves: solution.moves.into_iter().take(1).collect(),
+        }
+    }
+}
+
+/// 提示的来源
+#[derive(Clone, Copy, Debug)]
+pub enum HintSource<'a> {
+    /// 查表, 给出所有最佳移动
+    Tablebase(&'a Tablebase),
+    /// 从当前局面搜索, 只给出找到的解的第一步
+    Search {
+        algorithm: Algorithm,
+        rules: Rules,
+        limit: usize,
+    },
+}
+
+/// 给出 state 的下一步提示
+pub fn hint(state: &NodeValue, source: HintSource) -> anyhow::Result<Hint> {
+    match source {
+        HintSource::Tablebase(table) => {
+            Game::new(state, table.rules())?;
+            anyhow::ensure!(table.contains(state), "position not in tablebase");
+            let distance = table.distance(state).ok_or(SolveError::NoSolution)?;
+            Ok(Hint {
+                distance,
+                moves: table.best_moves(state),
+            })
+        }
+        HintSource::Search {
+            algorithm,
+            rules,
+            limit,
+        } => {
+            Game::new(state, &rules)?;
+            Ok(solve_with(state, limit, algorithm, rules)?.into())
+        }
+    }
+}
@@ -14,5 +14,408 @@ fn test() {
     use super::*;
 
     utils::init_log();
-    show_solve(&blocks(), 1024);
+    show_solve(blocks(), 1024);
+}
+
+#[test]
+fn test_mirror() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let mirror = state.mirror();
+    assert_ne!(state, mirror);
+    assert_eq!(mirror.mirror(), state);
+    assert_eq!(mirror.canonical(), state.canonical());
+
+    let solution = solve(&mirror, usize::MAX).unwrap();
+    assert_eq!(solution.len(), 8);
+    let first = solution.moves[0];
+    assert_eq!(mirror.get(first.from.0, first.from.1), Some(first.piece));
+}
+
+#[test]
+fn test_tablebase() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let table = Tablebase::build(&state, Rules::default(), usize::MAX).unwrap();
+    assert_eq!(table.distance(&state), Some(8));
+    assert_eq!(table.distance(&state.mirror()), Some(8));
+    assert_eq!(table.best_moves(&state)[0].to_string(), "(1,0) 右");
+
+    let mut data = Vec::new();
+    table.write_to(&mut data).unwrap();
+    assert_eq!(Tablebase::read_from(&data[..]).unwrap(), table);
+
+    data[30] ^= 1;
+    assert!(Tablebase::read_from(&data[..]).is_err());
+}
+
+#[test]
+fn test_hint() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let table = Tablebase::build(&state, Rules::default(), usize::MAX).unwrap();
+    let search = HintSource::Search {
+        algorithm: Algorithm::Bfs,
+        rules: Rules::default(),
+        limit: usize::MAX,
+    };
+    for source in [HintSource::Tablebase(&table), search] {
+        let ret = hint(&state, source).unwrap();
+        assert_eq!(ret.distance, 8);
+        assert_eq!(ret.moves[0].to_string(), "(1,0) 右");
+    }
+
+    let solved = parse_state("vhhv\nvppv\nvppv\nvccv\nxccx").unwrap();
+    let ret = hint(&solved, search).unwrap();
+    assert_eq!(ret.distance, 0);
+    assert!(ret.moves.is_empty());
+    let node = hrd_solve(&solved, usize::MAX).unwrap();
+    assert!(step_messages(&node).unwrap().is_empty());
+}
+
+#[test]
+fn test_moves() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve(&state, usize::MAX).unwrap();
+    let first = solution.moves[0];
+    assert_eq!(first.piece, BlockType::Vertical);
+    assert_eq!(first.from, (1, 0));
+    assert_eq!(first.path, Path::straight(Direction::Right, 1));
+    assert_eq!(first.to(), (2, 0));
+
+    let positions = solution.positions().unwrap();
+    assert_eq!(positions.len(), 9);
+    assert!(positions.last().unwrap().is_finish());
+
+    let illegal = Move {
+        path: Path::straight(Direction::Left, 1),
+        ..first
+    };
+    assert!(state.apply(&illegal, &Rules::default()).is_err());
+}
+
+#[test]
+fn test_validate() {
+    use super::*;
+
+    let problems = |state: &str| {
+        let e = parse_state(state).unwrap_err();
+        e.downcast::<ValidationError>().unwrap().problems
+    };
+
+    //底边上单独的纵向方块
+    let ret = problems("vvxv\nvvxv\nvvcc\nvvcc\npppv");
+    assert_eq!(ret.len(), 1);
+    assert_eq!(ret[0].pos, Some((3, 4)));
+    assert_eq!(ret[0].message, "vertical piece at (3,4) has no lower half");
+
+    //右边上单独的曹操, 所有问题一次给出
+    let ret = problems("vvxc\nvvxv\nvvcc\nvvcc\np?pp");
+    let messages: Vec<_> = ret.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "unknown token ? at (1,4)",
+            "CaoCao at (3,0) extends outside the board",
+            "vertical piece at (3,1) has no lower half",
+            "extra CaoCao at (2,2), there must be exactly one",
+        ]
+    );
+
+    let state = parse_state(blocks()).unwrap();
+    assert!(validate(&state, &Rules::default()).is_ok());
+}
+
+#[test]
+fn test_notation() {
+    use super::*;
+
+    let mut path = Path::straight(Direction::Up, 2);
+    path.push(Direction::Right);
+    let mv = Move {
+        piece: BlockType::Pawn,
+        from: (1, 3),
+        path,
+    };
+    assert_eq!(mv.to_string(), "(1,3) 上2右");
+    //超过上限时不会写坏已有的方向
+    assert_eq!(
+        Path::straight(Direction::Left, Path::MAX_LEN).len(),
+        Path::MAX_LEN
+    );
+    assert!(
+        std::panic::catch_unwind(|| Path::straight(Direction::Left, Path::MAX_LEN + 1)).is_err()
+    );
+    for (locale, coords, text) in [
+        ("zh-CN", "zero", "(1,3) 上2右"),
+        ("en", "zero", "(1,3) up 2, right"),
+        ("en", "one", "(2,4) up 2, right"),
+        ("zh", "chess", "b4 上2右"),
+    ] {
+        let notation = Notation {
+            locale: locale.parse().unwrap(),
+            coords: coords.parse().unwrap(),
+        };
+        assert_eq!(notation.format(&mv), text);
+    }
+    assert!("fr".parse::<Locale>().is_err());
+
+    //有名字的棋子用名字代替坐标
+    let (_, _, mut roster) = parse_named_puzzle("兵:小兵\nvvxv\nvvxv\nvvcc\nvvcc\npp兵p").unwrap();
+    let mv = Move {
+        piece: BlockType::Pawn,
+        from: (2, 4),
+        path: Path::straight(Direction::Up, 1),
+    };
+    let notation = Notation {
+        locale: Locale::En,
+        coords: Coords::Chess,
+    };
+    assert_eq!(roster.describe_with(&mv, &notation), "小兵 up");
+}
+
+#[test]
+fn test_session() {
+    use super::*;
+
+    //按解逐格移动, 同一棋子的直线移动合并为一步
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve(&state, 1024).unwrap();
+    let mut session = Session::new(&state, Rules::default()).unwrap();
+    for mv in solution.moves.iter() {
+        let (x, y) = mv.from;
+        let mut block = Block { ty: mv.piece, x, y };
+        for e in mv.path.iter() {
+            block = session.step(block, e).unwrap();
+        }
+    }
+    assert!(session.is_finish());
+    assert!(session.moves().eq(solution.moves.iter()));
+
+    let last = session.undo().unwrap();
+    assert!(!session.is_finish());
+    assert_eq!(session.redo(), Some(last));
+    assert_eq!(session.redo(), None);
+    assert!(session.is_finish());
+
+    //走回原处时去掉这一步, 并清空重做记录
+    let mut session = Session::new(&state, Rules::default()).unwrap();
+    let v = Block {
+        ty: BlockType::Vertical,
+        x: 1,
+        y: 0,
+    };
+    assert!(session.step(v, Direction::Left).is_err());
+    let moved = session.step(v, Direction::Right).unwrap();
+    assert_eq!(session.len(), 1);
+    session.undo();
+    session.step(v, Direction::Right).unwrap();
+    assert_eq!(session.redo(), None);
+    session.step(moved, Direction::Left).unwrap();
+    assert!(session.is_empty());
+    assert_eq!(session.state(), &state);
+
+    //图案终局允许没有棋子的局面, 但没有可以选择的棋子
+    let empty = "xxxx\n".repeat(5) + "goal\n" + &"????\n".repeat(5);
+    let (state, rules) = parse_puzzle(&empty).unwrap();
+    assert!(Session::new(&state, rules).is_err());
+}
+
+#[test]
+fn test_svg() {
+    use super::*;
+
+    let (state, rules, roster) =
+        parse_named_puzzle(&format!("曹:曹操\n{}", blocks().replace('c', "曹"))).unwrap();
+    let image = svg(&state, &rules, &roster, &SvgOptions::default());
+    assert!(image.starts_with("<svg") && image.ends_with("</svg>\n"));
+    //出口在底边正中
+    assert!(image.contains(r#"<rect x="50" y="210" width="80" height="10""#));
+    assert!(image.contains(">曹</text>"));
+    let options = SvgOptions {
+        labels: false,
+        ..Default::default()
+    };
+    assert!(!svg(&state, &rules, &roster, &options).contains("<text"));
+
+    //每个局面一个棋盘, 除终局外各有一个箭头
+    let solution = solve(&state, 1024).unwrap();
+    let image = storyboard(
+        &solution,
+        &roster,
+        &Notation::default(),
+        &SvgOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(image.matches("<g ").count(), solution.len() + 1);
+    assert_eq!(image.matches("<polyline").count(), solution.len());
+    assert!(image.contains("曹操 下"));
+}
+
+#[test]
+fn test_gif() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve(&state, 1024).unwrap();
+    let options = GifOptions {
+        tweens: 0,
+        ..Default::default()
+    };
+    let image = gif(&solution, &options).unwrap();
+    assert!(image.starts_with(b"GIF89a") && image.ends_with(b"\x3b"));
+    //4x5 的棋盘加上四周各 10 像素的边框
+    assert_eq!(image[6..10], [180, 0, 220, 0]);
+    //每个局面一帧
+    let frames = |image: &[u8]| image.windows(3).filter(|e| e == b"\x21\xf9\x04").count();
+    assert_eq!(frames(&image), solution.len() + 1);
+
+    //每移动一格多出 tweens - 1 帧过渡
+    let cells: usize = solution.moves.iter().map(|e| e.path.len()).sum();
+    let image = gif(&solution, &GifOptions::default()).unwrap();
+    assert!(frames(&image) >= solution.len() + 1 + cells * 3);
+
+    //解码后检查像素: 起止局面中曹操的位置, 边框和空格
+    let (width, palette, frames) = decode_gif(&gif(&solution, &options).unwrap());
+    assert_eq!(frames.len(), solution.len() + 1);
+    let pixel = |frame: &[u8], x: usize, y: usize| palette[frame[y * width + x] as usize];
+    let center = |x: usize, y: usize| (10 + x * 40 + 20, 10 + y * 40 + 20);
+    let (first, last) = (&frames[0], &frames[frames.len() - 1]);
+    assert_eq!(pixel(first, 0, 0), [107, 79, 58]);
+    for (frame, (x, y), rgb) in [
+        (first, center(2, 2), [217, 83, 79]),
+        (first, center(3, 3), [217, 83, 79]),
+        (first, center(2, 0), [243, 234, 216]),
+        (first, center(0, 4), [240, 173, 78]),
+        (last, center(1, 3), [217, 83, 79]),
+        (last, center(2, 4), [217, 83, 79]),
+    ] {
+        assert_eq!(pixel(frame, x, y), rgb, "({},{})", x, y);
+    }
+
+    //码表多次写满后重新开始
+    let mut seed = 1u32;
+    let data: Vec<u8> = (0..100_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 16) % 16) as u8
+        })
+        .collect();
+    assert_eq!(lzw_decode(4, &gif::lzw(&data)), data);
+
+    //过大的设置在分配像素之前拒绝
+    for options in [
+        GifOptions {
+            cell: u16::MAX,
+            ..Default::default()
+        },
+        GifOptions {
+            tweens: GifOptions::MAX_TWEENS + 1,
+            ..Default::default()
+        },
+    ] {
+        assert!(gif(&solution, &options).is_err());
+    }
+}
+
+/// 解码测试用的 GIF, 返回宽度, 全局调色板和叠加之后的每一帧
+#[cfg(test)]
+fn decode_gif(image: &[u8]) -> (usize, Vec<[u8; 3]>, Vec<Vec<u8>>) {
+    let u16_at = |i: usize| u16::from_le_bytes([image[i], image[i + 1]]) as usize;
+    let (width, height) = (u16_at(6), u16_at(8));
+    let colors = 2 << (image[10] & 7);
+    let palette: Vec<[u8; 3]> = image[13..13 + colors * 3]
+        .chunks(3)
+        .map(|e| [e[0], e[1], e[2]])
+        .collect();
+    //读出一串子块的内容
+    let sub_blocks = |pos: &mut usize| {
+        let mut ret = Vec::new();
+        while image[*pos] != 0 {
+            let len = image[*pos] as usize;
+            ret.extend_from_slice(&image[*pos + 1..*pos + 1 + len]);
+            *pos += 1 + len;
+        }
+        *pos += 1;
+        ret
+    };
+
+    let mut canvas = vec![0; width * height];
+    let mut frames = Vec::new();
+    let mut pos = 13 + colors * 3;
+    loop {
+        match image[pos] {
+            0x21 => {
+                pos += 2;
+                sub_blocks(&mut pos);
+            }
+            0x2c => {
+                let (x, y, w) = (u16_at(pos + 1), u16_at(pos + 3), u16_at(pos + 5));
+                let min_code_size = image[pos + 10];
+                pos += 11;
+                let pixels = lzw_decode(min_code_size, &sub_blocks(&mut pos));
+                for (i, row) in pixels.chunks(w).enumerate() {
+                    let start = (y + i) * width + x;
+                    canvas[start..start + w].copy_from_slice(row);
+                }
+                frames.push(canvas.clone());
+            }
+            0x3b => break,
+            e => panic!("unknown gif block {:#x}", e),
+        }
+    }
+    (width, palette, frames)
+}
+
+/// 按 GIF 规范解码变长 LZW, 与 gif.rs 的编码器分开实现
+#[cfg(test)]
+fn lzw_decode(min_code_size: u8, data: &[u8]) -> Vec<u8> {
+    let clear = 1usize << min_code_size;
+    let reset = || -> Vec<Vec<u8>> { (0..clear + 2).map(|e| vec![e as u8]).collect() };
+    let mut table = reset();
+    let mut size = min_code_size as u32 + 1;
+    let (mut acc, mut bits, mut pos) = (0u32, 0u32, 0);
+    let mut prev: Option<Vec<u8>> = None;
+    let mut ret = Vec::new();
+    loop {
+        while bits < size {
+            acc |= (data[pos] as u32) << bits;
+            pos += 1;
+            bits += 8;
+        }
+        let code = (acc & ((1 << size) - 1)) as usize;
+        acc >>= size;
+        bits -= size;
+        if code == clear {
+            table = reset();
+            size = min_code_size as u32 + 1;
+            prev = None;
+            continue;
+        }
+        if code == clear + 1 {
+            break;
+        }
+        let entry = match (table.get(code), &prev) {
+            (Some(e), _) => e.clone(),
+            //还没有加入码表的码只能是上一串加上它自己的第一个字节
+            (None, Some(p)) => [&p[..], &p[..1]].concat(),
+            (None, None) => panic!("bad lzw code {}", code),
+        };
+        if let Some(p) = prev
+            && table.len() < 4096
+        {
+            table.push([&p[..], &entry[..1]].concat());
+        }
+        if table.len() == 1 << size && size < 12 {
+            size += 1;
+        }
+        ret.extend_from_slice(&entry);
+        prev = Some(entry);
+    }
+    ret
 }
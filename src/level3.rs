@@ -14,5 +14,49 @@ fn test() {
     use super::*;
 
     utils::init_log();
-    show_solve(&blocks(), 1024);
-}
\ No newline at end of file
+    show_solve(blocks(), 1024);
+}
+
+#[test]
+fn test_bidirectional() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve_with(
+        &state,
+        usize::MAX,
+        Algorithm::Bidirectional,
+        Rules::default(),
+    )
+    .unwrap();
+    assert_eq!(solution.len(), 13);
+
+    //每放入一个局面检查上限, 相遇前达到上限时不再继续扩展这一层
+    let limit = solution.explored - 1;
+    let e = solve_with(&state, limit, Algorithm::Bidirectional, Rules::default()).unwrap_err();
+    assert_eq!(
+        e.downcast::<SolveError>().unwrap(),
+        SolveError::LimitExceeded(limit)
+    );
+
+    //图案终局有上亿种摆法, 放入终局时就要检查上限
+    let goal = "x????\n".to_string() + &"?????\n".repeat(7);
+    let puzzle = "ppppp\n".repeat(4) + &"xxxxx\n".repeat(4) + "goal\n" + &goal;
+    let (state, rules) = parse_puzzle(&puzzle).unwrap();
+    let e = solve_with(&state, 1000, Algorithm::Bidirectional, rules).unwrap_err();
+    assert_eq!(
+        e.downcast::<SolveError>().unwrap(),
+        SolveError::LimitExceeded(1000)
+    );
+}
+
+#[test]
+fn test_astar() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    for e in [Algorithm::AStar, Algorithm::IdaStar] {
+        let solution = solve_with(&state, usize::MAX, e, Rules::default()).unwrap();
+        assert_eq!(solution.len(), 13);
+    }
+}
@@ -0,0 +1,131 @@
+//横刀立马
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    vccv
+    vccv
+    vhhv
+    vppv
+    pxxp
+    "#
+}
+
+#[test]
+fn test() {
+    use super::*;
+
+    utils::init_log();
+    show_solve(blocks(), usize::MAX);
+}
+
+#[test]
+fn test_steps() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    assert_eq!(solve(&state, usize::MAX).unwrap().len(), 90);
+}
+
+#[test]
+fn test_bidirectional() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve_with(
+        &state,
+        usize::MAX,
+        Algorithm::Bidirectional,
+        Rules::default(),
+    )
+    .unwrap();
+    assert_eq!(solution.len(), 90);
+    assert_eq!(solution.start, state);
+}
+
+#[test]
+fn test_astar() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = AStar::default().solve(&state, usize::MAX).unwrap();
+    assert_eq!(solution.len(), 90);
+    assert!(solution.explored < solve(&state, usize::MAX).unwrap().explored);
+}
+
+#[test]
+fn test_parallel() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let expected = solve(&state, usize::MAX).unwrap();
+    for threads in [1, 3, 8] {
+        let solution = ParallelBfs {
+            threads,
+            ..Default::default()
+        }
+        .solve(&state, usize::MAX)
+        .unwrap();
+        assert_eq!(solution.moves, expected.moves);
+
+        //层中途达到上限也停止
+        for limit in [1, 100, expected.explored / 2] {
+            let e = ParallelBfs {
+                threads,
+                ..Default::default()
+            }
+            .solve(&state, limit)
+            .unwrap_err();
+            assert_eq!(
+                e.downcast::<SolveError>().unwrap(),
+                SolveError::LimitExceeded(limit)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_metric() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    for (metric, steps) in [(Metric::Cell, 116), (Metric::Piece, 81)] {
+        let solution = solve_with(
+            &state,
+            usize::MAX,
+            Algorithm::Bfs,
+            Rules {
+                metric,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(solution.len(), steps);
+        assert!(solution.positions().unwrap().last().unwrap().is_finish());
+        let solution = solve_with(
+            &state,
+            usize::MAX,
+            Algorithm::AStar,
+            Rules {
+                metric,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(solution.len(), steps);
+    }
+}
+
+#[test]
+fn test_generic_moves() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    let solution = solve(&state, usize::MAX).unwrap();
+    let rules = Rules::default();
+    for e in solution.positions().unwrap() {
+        let game = game::Game::new_unchecked(&e, &rules);
+        let mut generic = Vec::new();
+        game.generic_slide_nodes(&mut generic);
+        assert_eq!(generic, e.moves(&rules));
+    }
+}
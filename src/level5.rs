@@ -0,0 +1,79 @@
+//5x5 棋盘
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    vccvp
+    vccvp
+    phhvp
+    vhhvp
+    vpxxp
+    "#
+}
+
+#[test]
+fn test() {
+    use super::*;
+
+    utils::init_log();
+    show_solve(blocks(), usize::MAX);
+}
+
+#[test]
+fn test_size() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    assert_eq!(state.size(), (5, 5));
+    assert_eq!(NodeValue::from_bits(state.bits()).unwrap(), state);
+    assert_eq!(state.mirror().mirror(), state);
+
+    for e in [
+        Algorithm::Bfs,
+        Algorithm::Bidirectional,
+        Algorithm::AStar,
+        Algorithm::Parallel,
+    ] {
+        let solution = solve_with(&state, usize::MAX, e, Rules::default()).unwrap();
+        assert_eq!(solution.len(), 28);
+        assert!(solution.positions().unwrap().last().unwrap().is_finish());
+    }
+
+    //宽为奇数时曹操在中间偏左或偏右都算到达出口
+    let left = parse_state("xpxpx\nhhvhh\nppvpp\npccpp\npccpp").unwrap();
+    assert!(left.is_finish() && left.mirror().is_finish());
+
+    assert!(parse_state("vccv\nvccvp").is_err());
+    assert!(
+        parse_state("ccxx\nccxx\npppp\npppp\npppp\npppp\npppp\npppp\npppp\npppp\npppp").is_err()
+    );
+}
+
+#[test]
+fn test_code() {
+    use super::*;
+
+    let classic = parse_state("vccv\nvccv\nvhhv\nvppv\npxxp").unwrap();
+    assert_eq!(classic.encode(), "45LZLZTajcEg");
+
+    //格数为奇数时最后一个字符只有一格
+    let state = parse_state(blocks()).unwrap();
+    let code = state.encode();
+    assert_eq!(code.len(), 2 + 13);
+    assert!(
+        code.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+    );
+    assert_eq!(NodeValue::decode(&code).unwrap(), state);
+    assert_eq!(
+        NodeValue::decode(&state.mirror().encode()).unwrap(),
+        state.mirror()
+    );
+
+    assert!(NodeValue::decode("45LZLZTajc").is_err());
+    assert!(NodeValue::decode("45LZLZTaj!Eg").is_err());
+    assert!(NodeValue::decode("11A").is_err());
+    //多出的位不为 0
+    let mut bad = code.clone();
+    bad.replace_range(code.len() - 1.., "_");
+    assert!(NodeValue::decode(&bad).is_err());
+}
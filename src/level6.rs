@@ -0,0 +1,41 @@
+//3 个空格
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    pccp
+    pccp
+    vhhv
+    vppv
+    pxxx
+    "#
+}
+
+#[test]
+fn test() {
+    use super::*;
+
+    utils::init_log();
+    show_solve(blocks(), usize::MAX);
+}
+
+#[test]
+fn test_empty() {
+    use super::*;
+
+    let state = parse_state(blocks()).unwrap();
+    for (metric, steps) in [(Metric::Cell, 35), (Metric::Slide, 27), (Metric::Piece, 22)] {
+        for e in [Algorithm::Bfs, Algorithm::AStar] {
+            let solution = solve_with(
+                &state,
+                usize::MAX,
+                e,
+                Rules {
+                    metric,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(solution.len(), steps);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//自定义 L 形棋子
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    l=#./##
+    pccp
+    pccp
+    lxxp
+    llpp
+    pppp
+    "#
+}
+
+#[test]
+fn test_shapes() {
+    use super::*;
+
+    let (state, rules) = parse_puzzle(blocks()).unwrap();
+    for e in [
+        Algorithm::Bfs,
+        Algorithm::Bidirectional,
+        Algorithm::AStar,
+        Algorithm::IdaStar,
+        Algorithm::Parallel,
+    ] {
+        let solution = solve_with(&state, usize::MAX, e, rules).unwrap();
+        assert_eq!(solution.len(), 24);
+    }
+    assert!(parse_state(blocks()).is_err());
+    let l = rules.shapes.ty('l').unwrap();
+    let blocks = state.blocks(&rules);
+    assert_eq!(blocks.len(), 13);
+    assert!(blocks.contains(&Block { ty: l, x: 0, y: 2 }));
+
+    let table = Tablebase::build(&state, rules, usize::MAX).unwrap();
+    let mut data = Vec::new();
+    table.write_to(&mut data).unwrap();
+    let table = Tablebase::read_from(data.as_slice()).unwrap();
+    assert_eq!(table.rules(), &rules);
+    assert_eq!(table.distance(&state), Some(24));
+}
+
+#[test]
+fn test_without_caocao() {
+    use super::*;
+
+    //没有曹操, 终局为 L 形棋子到达左下角
+    let puzzle = "a=#./##\naxpp\naapx\nvxxv\nvppv\nxxxx";
+    let (state, rules) = parse_puzzle(&format!("{}\ngoal a 0,3", puzzle)).unwrap();
+    for e in [
+        Algorithm::Bfs,
+        Algorithm::Bidirectional,
+        Algorithm::AStar,
+        Algorithm::IdaStar,
+        Algorithm::Parallel,
+    ] {
+        let solution = solve_with(&state, usize::MAX, e, rules).unwrap();
+        assert_eq!(solution.len(), 5);
+    }
+    //从出口走出时仍然需要曹操
+    let e = parse_puzzle(puzzle).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "there must be exactly one CaoCao, found none"
+    );
+}
+
+#[test]
+fn test_parse_shape() {
+    use super::*;
+
+    let shape = Shape::parse("#./##").unwrap();
+    assert_eq!(shape.to_string(), "#./##");
+    assert_eq!(shape.len(), 3);
+    assert!(Shape::parse("#./.#").is_err());
+    assert!(Shape::parse("##/../##").is_err());
+    assert!(Shape::parse("#a").is_err());
+    assert!(parse_puzzle("c=#\npccp\npccp\nxxpp\npppp\npppp").is_err());
+}
+
+#[test]
+fn test_report() {
+    use super::*;
+
+    let (state, rules) = parse_puzzle(&format!("{}\ngoal c 1,3", blocks())).unwrap();
+    let rules = Rules {
+        metric: Metric::Piece,
+        ..rules
+    };
+    let report = Report {
+        solver: Algorithm::Bfs,
+        solution: solve_with(&state, usize::MAX, Algorithm::Bfs, rules).unwrap(),
+        elapsed_ms: 1,
+    };
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains(r##""shapes":[{"letter":"l","mask":"#./##"}],"goal":"c 1,3""##));
+    assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+
+    let csv = report.to_csv().unwrap();
+    assert_eq!(csv.lines().count(), report.solution.len() + 2);
+    assert!(csv.starts_with("step,piece,"));
+
+    //读回时检查每一步
+    let bad = json.replacen(r#""distance":1"#, r#""distance":3"#, 1);
+    assert!(serde_json::from_str::<Report>(&bad).is_err());
+    let bad = json.replacen(r#""distance":1"#, r#""distance":1000"#, 1);
+    assert!(serde_json::from_str::<Report>(&bad).is_err());
+}
+
+#[test]
+fn test_hint_report() {
+    use super::*;
+
+    let (state, rules) = parse_puzzle(blocks()).unwrap();
+    let source = HintSource::Search {
+        algorithm: Algorithm::Bfs,
+        rules,
+        limit: usize::MAX,
+    };
+    let hint = hint(&state, source).unwrap();
+    let json = serde_json::to_string(&HintReport::new(&hint, &rules.shapes)).unwrap();
+    let head = format!(r#"{{"distance":{},"moves":[{{"piece":"#, hint.distance);
+    assert!(json.starts_with(&head));
+
+    //问题带有位置, 供 serve 返回
+    let e = parse_state(
+        "ccv
+ccp",
+    )
+    .unwrap_err();
+    let json = serde_json::to_string(&e.downcast::<ValidationError>().unwrap()).unwrap();
+    assert_eq!(
+        json,
+        r#"{"problems":[{"pos":[2,0],"message":"vertical piece at (2,0) has no lower half"}]}"#
+    );
+}
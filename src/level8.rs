@@ -0,0 +1,100 @@
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    phhp
+    vccv
+    vccv
+    vppv
+    vxxv
+    "#
+}
+
+#[test]
+fn test_goal() {
+    use super::*;
+
+    for (goal, steps) in [
+        //与默认终局相同
+        ("goal c 1,3", 13),
+        ("goal\n????\n????\n????\n?cc?\n?cc?", 13),
+        ("goal p 0,4", 8),
+        //所有小兵在第一行
+        ("goal\npppp\n????\n????\n????\n????", 78),
+        //到达一个完整的局面
+        ("goal\ncchh\nccpp\nxxvv\nvvvv\nvvpp", 39),
+    ] {
+        let (state, rules) = parse_puzzle(&format!("{}\n{}", blocks(), goal)).unwrap();
+        for e in [
+            Algorithm::Bfs,
+            Algorithm::Bidirectional,
+            Algorithm::AStar,
+            Algorithm::Parallel,
+        ] {
+            let solution = solve_with(&state, usize::MAX, e, rules).unwrap();
+            assert_eq!(solution.len(), steps);
+            assert!(rules.is_finish(solution.positions().unwrap().last().unwrap()));
+        }
+    }
+
+    //表中记录终局条件
+    let (state, rules) = parse_puzzle(&format!("{}\ngoal p 0,4", blocks())).unwrap();
+    let table = Tablebase::build(&state, rules, usize::MAX).unwrap();
+    let mut data = Vec::new();
+    table.write_to(&mut data).unwrap();
+    let table = Tablebase::read_from(data.as_slice()).unwrap();
+    assert_eq!(table.rules(), &rules);
+    assert_eq!(table.distance(&state), Some(8));
+
+    //校验和正确但内容有问题的文件
+    let with_crc = |mut data: Vec<u8>| {
+        let len = data.len() - 4;
+        let crc = tablebase::crc32(0, &data[..len]);
+        data[len..].copy_from_slice(&crc.to_le_bytes());
+        data
+    };
+    let mut bad = data.clone();
+    //目标棋子的 x
+    bad[25] = 200;
+    assert!(Tablebase::read_from(with_crc(bad).as_slice()).is_err());
+    let mut bad = data.clone();
+    bad[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Tablebase::read_from(with_crc(bad).as_slice()).is_err());
+}
+
+#[test]
+fn test_parse_goal() {
+    use super::*;
+
+    let (_, rules) = parse_puzzle(&format!("{}\ngoal h (2,4)", blocks())).unwrap();
+    assert_eq!(
+        rules.goal,
+        Goal::Piece {
+            ty: BlockType::Horizontal,
+            x: 2,
+            y: 4
+        }
+    );
+    assert!(parse_puzzle(&format!("{}\ngoal h 3,4", blocks())).is_err());
+    assert!(parse_puzzle(&format!("{}\ngoal c 18446744073709551615,0", blocks())).is_err());
+    assert!(
+        !Goal::Piece {
+            ty: BlockType::CaoCao,
+            x: usize::MAX,
+            y: 0
+        }
+        .is_symmetric(&rules.shapes, 4)
+    );
+    assert!(parse_puzzle(&format!("{}\ngoal\n????", blocks())).is_err());
+    assert!(parse_state(&format!("{}\ngoal c 1,3", blocks())).is_err());
+
+    //文字形式
+    let (state, rules) =
+        parse_puzzle(&format!("{}\ngoal\npppp\n????\n????\n?xx?\n????", blocks())).unwrap();
+    let text = rules.goal.to_text(&rules.shapes);
+    assert_eq!(text, "pppp/????/????/?xx?/????");
+    assert_eq!(
+        Goal::from_text(&text, &rules.shapes, state.size()).unwrap(),
+        rules.goal
+    );
+    assert_eq!(Goal::Exit.to_text(&rules.shapes), "exit");
+}
@@ -0,0 +1,54 @@
+//横刀立马, 五虎将和曹操各用一个字标出
+#[cfg(test)]
+fn blocks() -> &'static str {
+    r#"
+    张:张飞
+    赵:赵云
+    马:马超
+    黄:黄忠
+    关:关羽
+    张曹曹赵
+    张曹曹赵
+    马关关黄
+    马pp黄
+    pxxp
+    "#
+}
+
+#[test]
+fn test_named() {
+    use super::*;
+
+    let (state, rules, mut roster) = parse_named_puzzle(blocks()).unwrap();
+    assert_eq!(state, parse_state("vccv\nvccv\nvhhv\nvppv\npxxp").unwrap());
+    assert_eq!(roster.pieces.len(), 6);
+
+    let solution = solve_with(&state, usize::MAX, Algorithm::Bfs, rules).unwrap();
+    assert_eq!(solution.len(), 90);
+    let mut steps = Vec::new();
+    for e in solution.moves.iter() {
+        steps.push(roster.describe(e));
+    }
+    assert!(steps.iter().any(|e| e.starts_with("关羽 ")));
+
+    //每个标出的棋子最后都在它应在的位置上
+    let last = solution.positions().unwrap().pop().unwrap();
+    let game = game::Game::new(&last, &rules).unwrap();
+    for e in roster.pieces.iter() {
+        assert!(game.blocks.contains(&e.block));
+    }
+}
+
+#[test]
+fn test_parse_named() {
+    use super::*;
+
+    let (_, _, roster) = parse_named_puzzle("c:曹操\nvccv\nvccv\nvhhv\nvppv\npxxp").unwrap();
+    assert_eq!(roster.pieces[0].name.as_deref(), Some("曹操"));
+    //同一个字母不连通, 或者没有这种形状
+    assert!(parse_named_puzzle("acca\nacca\nvhhv\nvppv\npxxp").is_err());
+    assert!(parse_named_puzzle("vccv\nvccv\naaav\nvppv\npxxp").is_err());
+    //有多个棋子的类型字母不能起名字
+    assert!(parse_named_puzzle("v:张飞\nvccv\nvccv\nvhhv\nvppv\npxxp").is_err());
+    assert!(parse_named_puzzle("z:张飞\nvccv\nvccv\nvhhv\nvppv\npxxp").is_err());
+}
@@ -0,0 +1,104 @@
+//! 华容道最短步骤求解, 棋盘大小由输入决定, 默认为经典的 4x5
+//!
+//! ```
+//! let board = hrd::parse_state(
+//!     r#"
+//!     vvxv
+//!     vvxv
+//!     vvcc
+//!     vvcc
+//!     pppp
+//!     "#,
+//! )
+//! .unwrap();
+//! let solution = hrd::solve(&board, 1024).unwrap();
+//! assert_eq!(solution.len(), 8);
+//! ```
+
+#[cfg(test)]
+mod level2;
+#[cfg(test)]
+mod level3;
+#[cfg(test)]
+mod level4;
+#[cfg(test)]
+mod level5;
+#[cfg(test)]
+mod level6;
+#[cfg(test)]
+mod level7;
+#[cfg(test)]
+mod level8;
+#[cfg(test)]
+mod level9;
+
+#[cfg(test)]
+mod utils;
+
+mod astar;
+mod bidirectional;
+mod board;
+mod game;
+mod gif;
+mod goal;
+mod hint;
+mod notation;
+mod parallel;
+mod report;
+mod roster;
+mod session;
+mod solve;
+mod svg;
+mod tablebase;
+mod validate;
+
+pub use astar::{AStar, IdaStar};
+pub use bidirectional::Bidirectional;
+pub use board::{
+    Block, BlockType, HEIGHT, MAX_CELLS, MAX_SIDE, NodeValue, Shape, Shapes, WIDTH,
+    parse_named_puzzle, parse_puzzle, parse_state,
+};
+pub use game::{Direction, Metric, Move, Path, Rules};
+pub use gif::{GifOptions, gif};
+pub use goal::{Goal, Pattern};
+pub use hint::{Hint, HintSource, hint};
+pub use notation::{Coords, Locale, MoveFormatter, Notation};
+pub use parallel::ParallelBfs;
+pub use report::{HintReport, Report};
+pub use roster::{NamedPiece, Roster};
+pub use session::Session;
+pub use solve::{
+    Algorithm, Bfs, Node, Solution, SolveError, Solver, hrd_solve, solve, solve_with, step_messages,
+};
+pub use svg::{SvgOptions, storyboard, svg};
+pub use tablebase::Tablebase;
+pub use validate::{Problem, ValidationError, validate};
+
+/// 棋盘局面
+pub type Board = NodeValue;
+/// 棋子类型
+pub type Piece = BlockType;
+
+#[cfg(test)]
+macro_rules! log_guard {
+    ($v:expr) => {{
+        match $v {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{}", e);
+                return;
+            }
+        }
+    }};
+}
+
+#[cfg(test)]
+fn show_solve(state: &str, limit: usize) {
+    let state = log_guard!(parse_state(state));
+    let ret = log_guard!(hrd_solve(&state, limit));
+    let steps = log_guard!(step_messages(&ret));
+    log::info!("{} steps", steps.len());
+    for e in steps {
+        log::info!("{}", e);
+    }
+}
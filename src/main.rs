@@ -1,625 +1,838 @@
-#![feature(hash_set_entry)]
-
 use std::{
-    collections::{HashSet, VecDeque},
-    rc::Rc,
+    io::Read,
+    process::ExitCode,
+    str::FromStr,
+    time::{Duration, Instant},
 };
 
-#[cfg(test)]
-mod level2;
-#[cfg(test)]
-mod level3;
+use hrd::{
+    Algorithm, GifOptions, HintSource, Metric, Notation, ParallelBfs, Report, Roster, Rules,
+    Session, SolveError, Solver, SvgOptions, Tablebase,
+};
+use log::LevelFilter;
 
+mod play;
+mod serve;
+mod tui;
 mod utils;
 
-fn main() -> anyhow::Result<()> {
-    utils::init_log();
-
-    let state = r#"
-    vvxv
-    vvxv
-    vvcc
-    vvcc
-    pppp
-    "#;
-    let state = parse_state(state)?;
-    let ret = hrd_solve(&state, 1024)?;
-    let steps = step_messages(&ret)?;
-    log::info!("{} steps", steps.len());
-    for e in steps {
-        log::info!("{}", e);
-    }
+const USAGE: &str = "\
+用法:
+    hrd solve [选项] <文件|->                 求解文件中的局面, - 表示从 stdin 读取
+    hrd hint [选项] <文件|->                  给出局面的剩余步数和下一步
+    hrd play-solution [选项] <文件|->         求解后在终端中逐步回放
+    hrd play [选项] <文件|->                  在终端中玩, 可以撤销, 重做和提示
+    hrd tablebase build [选项] <文件|-> -o <表文件>
+                                             计算局面所在连通分量中每个局面到终局的步数并保存
+    hrd tablebase query [选项] <表文件> <文件|->
+                                             查询局面到终局的步数和最佳移动
+    hrd code <文件|->                         输出局面的单行局面码
+    hrd svg [选项] <文件|->                   画出局面的 SVG, --storyboard 时画出解的每一步
+    hrd render --gif [选项] <文件|-> -o <图片> 求解后把解画成 GIF 动画
+    hrd serve [选项]                          在本机启动 HTTP JSON 服务, 接口 /solve /hint /validate /render
+    hrd help                                 显示本帮助
+
+需要局面文件的地方都可以直接给出局面码, 如 hrd solve 45LZLZTajcEg
+
+选项:
+    -l, --limit <N>             搜索局面数量上限 (默认不限制, serve 中为每个请求的上限, 默认 1000000)
+    -a, --algorithm <ALG>       搜索算法 bfs|bidirectional|astar|idastar|parallel (默认 bfs)
+    -m, --metric <METRIC>       计步方式: cell 每格一步, slide 直线滑动一步, piece 同一棋子连续移动一步 (默认 slide)
+    -j, --threads <N>           parallel 使用的线程数, serve 中为同时处理的请求数 (默认 CPU 核心数)
+    -f, --format <FORMAT>       输出格式: text 输出到 stdout, log 通过日志输出, json 完整结果, csv 每步一行 (默认 text)
+    -o, --output <FILE>         输出文件
+    -t, --tablebase <FILE>      hint 查表而不是搜索
+    -d, --delay <MS>            回放和 GIF 中每步的间隔毫秒数 (默认 500)
+        --storyboard            svg 画出解经过的所有局面和每步的箭头
+        --columns <N>           分镜每行的局面数 (默认 5)
+        --cell <PX>             图片中每格的边长 (默认 40, GIF 最大 160)
+        --tweens <N>            GIF 棋子每移动一格的过渡帧数, 0 表示不要过渡 (默认 4, 最多 30)
+        --no-labels             图片中不标出棋子的字母
+        --lang <zh-CN|en>       移动说明的语言 (默认 zh-CN)
+        --coords <STYLE>        坐标写法: zero 从 0 开始 (1,3), one 从 1 开始 (2,4), chess 列用字母 b4 (默认 zero)
+        --port <PORT>           serve 监听的端口 (默认 8080)
+        --timeout <MS>          serve 中每个请求的最长时间, 超时的搜索会被取消 (默认 10000)
+        --log-level <LEVEL>     日志级别 off|error|warn|info|debug|trace (默认 warn, log 格式下为 info)
+
+退出码:
+    0 已求解  1 无解  2 超出搜索上限  3 输入错误  4 参数错误";
+
+const EXIT_UNSOLVABLE: u8 = 1;
+const EXIT_LIMIT_EXCEEDED: u8 = 2;
+const EXIT_BAD_INPUT: u8 = 3;
+const EXIT_USAGE: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Log,
+    Json,
+    Csv,
+}
 
-    Ok(())
+#[derive(Debug)]
+struct SolveArgs {
+    input: String,
+    limit: usize,
+    algorithm: Algorithm,
+    metric: Metric,
+    threads: Option<usize>,
+    format: Format,
+    notation: Notation,
+    log_level: LevelFilter,
 }
 
-fn step_messages(node: &Node) -> anyhow::Result<Vec<String>> {
-    anyhow::ensure!(node.parent.is_some(), "node is last, no message");
+#[derive(Debug)]
+struct HintArgs {
+    input: String,
+    tablebase: Option<String>,
+    limit: usize,
+    algorithm: Algorithm,
+    metric: Metric,
+    notation: Notation,
+    log_level: LevelFilter,
+}
 
-    let mut steps: Vec<String> = Vec::new();
-    let mut current_node = node;
-    let mut current_game = Game::new_unchecked(&current_node.val);
+#[derive(Debug)]
+struct PlayArgs {
+    input: String,
+    limit: usize,
+    algorithm: Algorithm,
+    metric: Metric,
+    delay: Duration,
+    notation: Notation,
+}
 
-    while let Some(prev_node) = current_node.parent.as_deref() {
-        let prev_game = Game::new_unchecked(&prev_node.val);
-        let msg = prev_game.move_message(&current_game.state)?;
-        steps.push(msg);
-        current_node = prev_node;
-        current_game = prev_game;
-    }
-    steps.reverse();
-    return Ok(steps);
-}
-
-#[cfg(test)]
-fn show_solve(state: &str, limit: usize) {
-    let state = log_guard!(parse_state(state));
-    let ret = log_guard!(hrd_solve(&state, limit));
-    let steps = log_guard!(step_messages(&ret));
-    log::info!("{} steps", steps.len());
-    for e in steps {
-        log::info!("{}", e);
+#[derive(Debug)]
+struct SvgArgs {
+    input: String,
+    output: Option<String>,
+    storyboard: bool,
+    options: SvgOptions,
+    limit: usize,
+    algorithm: Algorithm,
+    metric: Metric,
+    notation: Notation,
+}
+
+#[derive(Debug)]
+struct RenderArgs {
+    input: String,
+    output: String,
+    options: GifOptions,
+    limit: usize,
+    algorithm: Algorithm,
+    metric: Metric,
+}
+
+#[derive(Debug)]
+struct ServeArgs {
+    options: serve::Options,
+    log_level: LevelFilter,
+}
+
+#[derive(Debug)]
+struct TablebaseBuildArgs {
+    input: String,
+    output: String,
+    limit: usize,
+    metric: Metric,
+    log_level: LevelFilter,
+}
+
+#[derive(Debug)]
+struct TablebaseQueryArgs {
+    table: String,
+    input: String,
+    notation: Notation,
+}
+
+#[derive(Debug)]
+struct CodeArgs {
+    input: String,
+}
+
+#[derive(Debug)]
+enum Command {
+    Help,
+    Solve(SolveArgs),
+    Hint(HintArgs),
+    Play(PlayArgs),
+    Game(PlayArgs),
+    TablebaseBuild(TablebaseBuildArgs),
+    TablebaseQuery(TablebaseQueryArgs),
+    Code(CodeArgs),
+    Svg(SvgArgs),
+    Render(RenderArgs),
+    Serve(ServeArgs),
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Command::Solve(args) => run_solve(&args),
+        Command::Hint(args) => run_hint(&args),
+        Command::Play(args) => run_play(&args),
+        Command::Game(args) => run_game(&args),
+        Command::TablebaseBuild(args) => run_tablebase_build(&args),
+        Command::TablebaseQuery(args) => run_tablebase_query(&args),
+        Command::Code(args) => run_code(&args),
+        Command::Svg(args) => run_svg(&args),
+        Command::Render(args) => run_render(&args),
+        Command::Serve(args) => run_serve(args),
     }
 }
 
-fn parse_state(state: &str) -> anyhow::Result<NodeValue> {
-    let mut blocks: NodeValue = Default::default();
-    let mut x = 0;
-    let mut y = 0;
-    for line in state.lines().map(|e| e.trim()).filter(|e| !e.is_empty()) {
-        x = 0;
-        for c in line.chars() {
-            let val = match c {
-                'c' => Some(BlockType::CaoCao),
-                'h' => Some(BlockType::Horizontal),
-                'v' => Some(BlockType::Vertical),
-                'p' => Some(BlockType::Pawn),
-                'x' => None,
-                _ => anyhow::bail!("unknown token {}", c),
-            };
-            blocks.set(x, y, val);
-            x += 1;
+/// 逐个取出选项, 位置参数收集到 positional 中
+struct ArgParser<'a> {
+    args: std::slice::Iter<'a, String>,
+    positional: Vec<&'a str>,
+}
+
+impl<'a> ArgParser<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self {
+            args: args.iter(),
+            positional: Vec::new(),
+        }
+    }
+
+    fn next_option(&mut self) -> Option<&'a str> {
+        for arg in self.args.by_ref() {
+            if arg.starts_with('-') && arg != "-" {
+                return Some(arg);
+            }
+            self.positional.push(arg);
         }
-        y += 1;
+        None
+    }
+
+    fn value(&mut self, name: &str) -> anyhow::Result<&'a str> {
+        self.args
+            .next()
+            .map(|e| e.as_str())
+            .ok_or_else(|| anyhow::anyhow!("{} requires a value", name))
     }
-    anyhow::ensure!(y == HEIGHT && x == WIDTH, "size error {}x{}", y, x);
 
-    return Ok(blocks);
+    fn parse<T: FromStr>(&mut self, name: &str) -> anyhow::Result<T> {
+        let v = self.value(name)?;
+        v.parse()
+            .map_err(|_| anyhow::anyhow!("invalid value {} for {}", v, name))
+    }
+
+    /// 取出恰好 N 个位置参数
+    fn positional<const N: usize>(&self, names: [&str; N]) -> anyhow::Result<[String; N]> {
+        if let Some(e) = self.positional.get(N) {
+            anyhow::bail!("unexpected argument {}", e);
+        }
+        let mut ret = names.map(|_| String::new());
+        for (i, name) in names.iter().enumerate() {
+            ret[i] = self
+                .positional
+                .get(i)
+                .ok_or_else(|| anyhow::anyhow!("missing {}", name))?
+                .to_string();
+        }
+        Ok(ret)
+    }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum BlockType {
-    CaoCao,
-    Horizontal,
-    Vertical,
-    Pawn,
+fn parse_args(args: &[String]) -> anyhow::Result<Command> {
+    match args.first().map(|e| e.as_str()) {
+        Some("solve") => parse_solve_args(&args[1..]),
+        Some("hint") => parse_hint_args(&args[1..]),
+        Some("play-solution") => parse_play_args(&args[1..], Command::Play),
+        Some("play") => parse_play_args(&args[1..], Command::Game),
+        Some("tablebase") => match args.get(1).map(|e| e.as_str()) {
+            Some("build") => parse_tablebase_build_args(&args[2..]),
+            Some("query") => parse_tablebase_query_args(&args[2..]),
+            Some(e) => anyhow::bail!("unknown tablebase command {}", e),
+            None => anyhow::bail!("missing tablebase command"),
+        },
+        Some("code") => parse_code_args(&args[1..]),
+        Some("svg") => parse_svg_args(&args[1..]),
+        Some("render") => parse_render_args(&args[1..]),
+        Some("serve") => parse_serve_args(&args[1..]),
+        Some("help" | "-h" | "--help") | None => Ok(Command::Help),
+        Some(e) => anyhow::bail!("unknown command {}", e),
+    }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-struct Block {
-    ty: BlockType,
-    x: usize,
-    y: usize,
+fn parse_solve_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut limit = usize::MAX;
+    let mut algorithm = Algorithm::default();
+    let mut metric = Metric::default();
+    let mut threads = None;
+    let mut format = Format::Text;
+    let mut notation = Notation::default();
+    let mut log_level = None;
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-a" | "--algorithm" => algorithm = parser.value(arg)?.parse()?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "-j" | "--threads" => threads = Some(parser.parse(arg)?),
+            "-f" | "--format" => {
+                format = match parser.value(arg)? {
+                    "text" => Format::Text,
+                    "log" => Format::Log,
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    v => anyhow::bail!("unknown format {}", v),
+                };
+            }
+            "--lang" => notation.locale = parser.value(arg)?.parse()?,
+            "--coords" => notation.coords = parser.value(arg)?.parse()?,
+            "--log-level" => log_level = Some(parser.parse(arg)?),
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
+        }
+    }
+
+    let [input] = parser.positional(["input file"])?;
+    let log_level = log_level.unwrap_or(match format {
+        Format::Log => LevelFilter::Info,
+        _ => LevelFilter::Warn,
+    });
+    Ok(Command::Solve(SolveArgs {
+        input,
+        limit,
+        algorithm,
+        metric,
+        threads,
+        format,
+        notation,
+        log_level,
+    }))
 }
 
-const WIDTH: usize = 4;
-const HEIGHT: usize = 5;
+fn parse_hint_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut tablebase = None;
+    let mut limit = usize::MAX;
+    let mut algorithm = Algorithm::default();
+    let mut metric = Metric::default();
+    let mut notation = Notation::default();
+    let mut log_level = LevelFilter::Warn;
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "-t" | "--tablebase" => tablebase = Some(parser.value(arg)?.to_string()),
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-a" | "--algorithm" => algorithm = parser.value(arg)?.parse()?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "--lang" => notation.locale = parser.value(arg)?.parse()?,
+            "--coords" => notation.coords = parser.value(arg)?.parse()?,
+            "--log-level" => log_level = parser.parse(arg)?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
+        }
+    }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Default)]
-struct NodeValue([[Option<BlockType>; WIDTH]; HEIGHT]);
+    let [input] = parser.positional(["input file"])?;
+    Ok(Command::Hint(HintArgs {
+        input,
+        tablebase,
+        limit,
+        algorithm,
+        metric,
+        notation,
+        log_level,
+    }))
+}
 
-impl NodeValue {
-    fn is_finish(&self) -> bool {
-        self.0[HEIGHT - 1][1] == Some(BlockType::CaoCao)
-            && self.0[HEIGHT - 1][2] == Some(BlockType::CaoCao)
+/// play-solution 和 play 共用的选项, command 给出对应的命令
+fn parse_play_args(args: &[String], command: fn(PlayArgs) -> Command) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut limit = usize::MAX;
+    let mut algorithm = Algorithm::default();
+    let mut metric = Metric::default();
+    let mut delay = Duration::from_millis(500);
+    let mut notation = Notation::default();
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-a" | "--algorithm" => algorithm = parser.value(arg)?.parse()?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "-d" | "--delay" => delay = Duration::from_millis(parser.parse(arg)?),
+            "--lang" => notation.locale = parser.value(arg)?.parse()?,
+            "--coords" => notation.coords = parser.value(arg)?.parse()?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
+        }
     }
 
-    fn set(&mut self, x: usize, y: usize, val: Option<BlockType>) {
-        self.0[y][x] = val
+    let [input] = parser.positional(["input file"])?;
+    Ok(command(PlayArgs {
+        input,
+        limit,
+        algorithm,
+        metric,
+        delay,
+        notation,
+    }))
+}
+
+fn parse_tablebase_build_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut output = None;
+    let mut limit = usize::MAX;
+    let mut metric = Metric::default();
+    let mut log_level = LevelFilter::Warn;
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "-o" | "--output" => output = Some(parser.value(arg)?.to_string()),
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "--log-level" => log_level = parser.parse(arg)?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
+        }
     }
 
-    fn get(&self, x: usize, y: usize) -> Option<BlockType> {
-        self.0[y][x]
+    let [input] = parser.positional(["input file"])?;
+    let output = output.ok_or_else(|| anyhow::anyhow!("missing --output"))?;
+    Ok(Command::TablebaseBuild(TablebaseBuildArgs {
+        input,
+        output,
+        limit,
+        metric,
+        log_level,
+    }))
+}
+
+fn parse_tablebase_query_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut notation = Notation::default();
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "--lang" => notation.locale = parser.value(arg)?.parse()?,
+            "--coords" => notation.coords = parser.value(arg)?.parse()?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
+        }
     }
+
+    let [table, input] = parser.positional(["table file", "input file"])?;
+    Ok(Command::TablebaseQuery(TablebaseQueryArgs {
+        table,
+        input,
+        notation,
+    }))
 }
 
-#[derive(Debug)]
-struct Node {
-    val: NodeValue,
-    parent: Option<Rc<Node>>,
-}
-
-struct Game {
-    blocks: [Block; 10],
-
-    state: NodeValue,
-    empty_cell: [(usize, usize); 2],
-}
-
-impl Game {
-    fn new_unchecked(state: &NodeValue) -> Self {
-        let mut blocks = [Block {
-            ty: BlockType::Pawn,
-            x: 0,
-            y: 0,
-        }; 10];
-        let mut block_idx = 0;
-
-        let mut visited = [[false; WIDTH]; HEIGHT];
-
-        let mut empty_cell = [(0, 0); 2];
-        let mut empty_cell_idx = 0;
-
-        for y in 0..HEIGHT {
-            for x in 0..WIDTH {
-                match state.get(x, y) {
-                    Some(BlockType::CaoCao) => {
-                        if visited[y][x] == false {
-                            blocks[block_idx] = Block {
-                                ty: BlockType::CaoCao,
-                                x,
-                                y,
-                            };
-                            visited[y][x] = true;
-                            visited[y][x + 1] = true;
-                            visited[y + 1][x] = true;
-                            visited[y + 1][x + 1] = true;
-                            block_idx += 1;
-                        }
-                    }
-                    Some(BlockType::Horizontal) => {
-                        if visited[y][x] == false {
-                            blocks[block_idx] = Block {
-                                ty: BlockType::Horizontal,
-                                x,
-                                y,
-                            };
-                            visited[y][x] = true;
-                            visited[y][x + 1] = true;
-                            block_idx += 1;
-                        }
-                    }
-                    Some(BlockType::Vertical) => {
-                        if visited[y][x] == false {
-                            blocks[block_idx] = Block {
-                                ty: BlockType::Vertical,
-                                x,
-                                y,
-                            };
-                            visited[y][x] = true;
-                            visited[y + 1][x] = true;
-                            block_idx += 1;
-                        }
-                    }
-                    Some(BlockType::Pawn) => {
-                        blocks[block_idx] = Block {
-                            ty: BlockType::Pawn,
-                            x,
-                            y,
-                        };
-                        visited[y][x] = true;
-                        block_idx += 1;
-                    }
-                    None => {
-                        empty_cell[empty_cell_idx] = (x, y);
-                        empty_cell_idx += 1;
-                    }
-                }
-            }
+fn parse_code_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    if let Some(arg) = parser.next_option() {
+        match arg {
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
         }
+    }
 
-        Self {
-            blocks,
-            state: state.clone(),
-            empty_cell,
+    let [input] = parser.positional(["input file"])?;
+    Ok(Command::Code(CodeArgs { input }))
+}
+
+fn parse_svg_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut output = None;
+    let mut storyboard = false;
+    let mut options = SvgOptions::default();
+    let mut limit = usize::MAX;
+    let mut algorithm = Algorithm::default();
+    let mut metric = Metric::default();
+    let mut notation = Notation::default();
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "-o" | "--output" => output = Some(parser.value(arg)?.to_string()),
+            "--storyboard" => storyboard = true,
+            "--columns" => options.columns = parser.parse(arg)?,
+            "--cell" => options.cell = parser.parse(arg)?,
+            "--no-labels" => options.labels = false,
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-a" | "--algorithm" => algorithm = parser.value(arg)?.parse()?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "--lang" => notation.locale = parser.value(arg)?.parse()?,
+            "--coords" => notation.coords = parser.value(arg)?.parse()?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
         }
     }
 
-    fn new(state: &NodeValue) -> anyhow::Result<Self> {
-        let mut blocks = Vec::with_capacity(10);
-        let mut visited = [[false; WIDTH]; HEIGHT];
-        let mut empty_cell = Vec::with_capacity(2);
-        for y in 0..HEIGHT {
-            for x in 0..WIDTH {
-                match state.get(x, y) {
-                    Some(BlockType::CaoCao) => {
-                        if visited[y][x] == false {
-                            anyhow::ensure!(visited[y][x + 1] == false);
-                            anyhow::ensure!(visited[y + 1][x] == false);
-                            anyhow::ensure!(visited[y + 1][x + 1] == false);
-                            blocks.push(Block {
-                                ty: BlockType::CaoCao,
-                                x,
-                                y,
-                            });
-
-                            visited[y][x] = true;
-                            visited[y][x + 1] = true;
-                            visited[y + 1][x] = true;
-                            visited[y + 1][x + 1] = true;
-                        }
-                    }
-                    Some(BlockType::Horizontal) => {
-                        if visited[y][x] == false {
-                            anyhow::ensure!(visited[y][x + 1] == false);
-                            blocks.push(Block {
-                                ty: BlockType::Horizontal,
-                                x,
-                                y,
-                            });
-                            visited[y][x] = true;
-                            visited[y][x + 1] = true;
-                        }
-                    }
-                    Some(BlockType::Vertical) => {
-                        if visited[y][x] == false {
-                            anyhow::ensure!(visited[y + 1][x] == false);
-                            blocks.push(Block {
-                                ty: BlockType::Vertical,
-                                x,
-                                y,
-                            });
-
-                            visited[y][x] = true;
-                            visited[y + 1][x] = true;
-                        }
-                    }
-                    Some(BlockType::Pawn) => {
-                        anyhow::ensure!(visited[y][x] == false);
-                        blocks.push(Block {
-                            ty: BlockType::Pawn,
-                            x,
-                            y,
-                        });
-                        visited[y][x] = true;
-                    }
-                    None => {
-                        empty_cell.push((x, y));
-                    }
-                }
-            }
+    let [input] = parser.positional(["input file"])?;
+    Ok(Command::Svg(SvgArgs {
+        input,
+        output,
+        storyboard,
+        options,
+        limit,
+        algorithm,
+        metric,
+        notation,
+    }))
+}
+
+fn parse_render_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut gif = false;
+    let mut output = None;
+    let mut options = GifOptions::default();
+    let mut limit = usize::MAX;
+    let mut algorithm = Algorithm::default();
+    let mut metric = Metric::default();
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "--gif" => gif = true,
+            "-o" | "--output" => output = Some(parser.value(arg)?.to_string()),
+            "--cell" => options.cell = parser.parse(arg)?,
+            //GIF 的时间单位是 1/100 秒
+            "-d" | "--delay" => options.delay = parser.parse::<u16>(arg)?.div_ceil(10),
+            "--tweens" => options.tweens = parser.parse(arg)?,
+            "-l" | "--limit" => limit = parser.parse(arg)?,
+            "-a" | "--algorithm" => algorithm = parser.value(arg)?.parse()?,
+            "-m" | "--metric" => metric = parser.value(arg)?.parse()?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
         }
-        let cc_num = blocks.iter().filter(|b| b.ty == BlockType::CaoCao).count();
-        if cc_num != 1 {
-            anyhow::bail!("There must be exactly one CaoCao block, found {}", cc_num);
+    }
+
+    //目前只支持 GIF
+    anyhow::ensure!(gif, "render needs an image format, e.g. --gif");
+    let output = output.ok_or_else(|| anyhow::anyhow!("missing output file, use -o"))?;
+    let [input] = parser.positional(["input file"])?;
+    Ok(Command::Render(RenderArgs {
+        input,
+        output,
+        options,
+        limit,
+        algorithm,
+        metric,
+    }))
+}
+
+fn parse_serve_args(args: &[String]) -> anyhow::Result<Command> {
+    let mut parser = ArgParser::new(args);
+    let mut options = serve::Options {
+        port: 8080,
+        workers: std::thread::available_parallelism().map_or(1, |e| e.get()),
+        limit: 1_000_000,
+        timeout: Duration::from_secs(10),
+    };
+    let mut log_level = LevelFilter::Info;
+    while let Some(arg) = parser.next_option() {
+        match arg {
+            "--port" => options.port = parser.parse(arg)?,
+            "-j" | "--threads" => options.workers = parser.parse(arg)?,
+            "-l" | "--limit" => options.limit = parser.parse(arg)?,
+            "--timeout" => options.timeout = Duration::from_millis(parser.parse(arg)?),
+            "--log-level" => log_level = parser.parse(arg)?,
+            "-h" | "--help" => return Ok(Command::Help),
+            v => anyhow::bail!("unknown option {}", v),
         }
+    }
 
-        let blocks = <[Block; 10]>::try_from(blocks)
-            .map_err(|e| anyhow::anyhow!("block must be 10, but get {}", e.len()))?;
+    parser.positional([])?;
+    Ok(Command::Serve(ServeArgs { options, log_level }))
+}
 
-        let empty_cell = <[(usize, usize); 2]>::try_from(empty_cell)
-            .map_err(|e| anyhow::anyhow!("empty cell must be 2, but get {}", e.len()))?;
+fn read_input(input: &str) -> anyhow::Result<String> {
+    if input == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(input).map_err(|e| anyhow::anyhow!("read {} failed: {}", input, e))
+    }
+}
+
+/// 读取局面和其中定义的自定义形状, 终局与单独标出的棋子, 计步方式由参数给出
+///
+/// 不存在同名文件时 input 也可以是局面码, 局面码只记录局面, 规则为默认值
+fn read_state(input: &str, metric: Metric) -> anyhow::Result<(hrd::NodeValue, Rules, Roster)> {
+    if input != "-"
+        && !std::path::Path::new(input).exists()
+        && hrd::NodeValue::decode(input).is_ok()
+    {
+        return parse_board(input, metric);
+    }
+    parse_board(&read_input(input)?, metric)
+}
+
+/// 解析局面码或者局面文本
+fn parse_board(text: &str, metric: Metric) -> anyhow::Result<(hrd::NodeValue, Rules, Roster)> {
+    if let Ok(state) = hrd::NodeValue::decode(text.trim()) {
+        let rules = Rules {
+            metric,
+            ..Rules::default()
+        };
+        hrd::validate(&state, &rules)?;
+        return Ok((state, rules, Roster::default()));
+    }
+    let (state, rules, roster) = hrd::parse_named_puzzle(text)?;
+    Ok((state, Rules { metric, ..rules }, roster))
+}
 
-        Ok(Self {
-            blocks,
-            state: state.clone(),
-            empty_cell,
-        })
+/// 局面定义的形状和终局须与表中记录的一致
+fn check_rules(table: &Tablebase, rules: &Rules) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        table.rules().shapes == rules.shapes,
+        "custom shapes mismatch the tablebase"
+    );
+    anyhow::ensure!(
+        table.rules().goal == rules.goal,
+        "goal mismatch the tablebase"
+    );
+    Ok(())
+}
+
+/// 输出错误并按错误类型给出退出码, 搜索之外的错误都视为输入错误
+fn fail(e: anyhow::Error) -> ExitCode {
+    eprintln!("{}", e);
+    match e.downcast_ref::<SolveError>() {
+        Some(SolveError::NoSolution) => ExitCode::from(EXIT_UNSOLVABLE),
+        Some(SolveError::LimitExceeded(_) | SolveError::Cancelled) => {
+            ExitCode::from(EXIT_LIMIT_EXCEEDED)
+        }
+        None => ExitCode::from(EXIT_BAD_INPUT),
     }
+}
+
+fn run_solve(args: &SolveArgs) -> ExitCode {
+    utils::init_stderr_log(args.log_level);
 
-    fn next_nodes(&self, ret: &mut Vec<NodeValue>) {
-        for e in self.blocks.iter() {
-            let x = e.x;
-            let y = e.y;
-            match e.ty {
-                BlockType::CaoCao => {
-                    //上移
-                    if y >= 1 && self.empty_cell == [(x, y - 1), (x + 1, y - 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 1, Some(e.ty));
-                        node.set(x + 1, y - 1, Some(e.ty));
-                        node.set(x, y + 1, None);
-                        node.set(x + 1, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //下移
-                    if y < HEIGHT - 2 && self.empty_cell == [(x, y + 2), (x + 1, y + 2)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 2, Some(e.ty));
-                        node.set(x + 1, y + 2, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-
-                    //左移
-                    if x >= 1 && self.empty_cell == [(x - 1, y), (x - 1, y + 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x - 1, y, Some(e.ty));
-                        node.set(x - 1, y + 1, Some(e.ty));
-                        node.set(x + 1, y, None);
-                        node.set(x + 1, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //右移
-                    if x < WIDTH - 2 && self.empty_cell == [(x + 2, y), (x + 2, y + 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x + 2, y, Some(e.ty));
-                        node.set(x + 2, y + 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-                }
-                BlockType::Horizontal => {
-                    //上移
-                    if y >= 1 && self.empty_cell == [(x, y - 1), (x + 1, y - 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 1, Some(e.ty));
-                        node.set(x + 1, y - 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-
-                    //下移
-                    if y < HEIGHT - 1 && self.empty_cell == [(x, y + 1), (x + 1, y + 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 1, Some(e.ty));
-                        node.set(x + 1, y + 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-
-                    //左移一格
-                    if x >= 1 && self.empty_cell.contains(&(x - 1, y)) {
-                        let mut node = self.state.clone();
-                        node.set(x - 1, y, Some(e.ty));
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-
-                    //左移二格
-                    if x >= 2 && self.empty_cell == [(x - 2, y), (x - 1, y)] {
-                        let mut node = self.state.clone();
-                        node.set(x - 2, y, Some(e.ty));
-                        node.set(x - 1, y, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-
-                    //右移一格
-                    if x < WIDTH - 2 && self.empty_cell.contains(&(x + 2, y)) {
-                        let mut node = self.state.clone();
-                        node.set(x + 2, y, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //右移二格
-                    if x < WIDTH - 3 && self.empty_cell == [(x + 2, y), (x + 3, y)] {
-                        let mut node = self.state.clone();
-                        node.set(x + 2, y, Some(e.ty));
-                        node.set(x + 3, y, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x + 1, y, None);
-                        ret.push(node);
-                    }
-                }
-                BlockType::Vertical => {
-                    //上移一格
-                    if y >= 1 && self.empty_cell.contains(&(x, y - 1)) {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 1, Some(e.ty));
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //上移二格
-                    if y >= 2 && self.empty_cell == [(x, y - 2), (x, y - 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 2, Some(e.ty));
-                        node.set(x, y - 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //下移一格
-                    if y < HEIGHT - 2 && self.empty_cell.contains(&(x, y + 2)) {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 2, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //下移二格
-                    if y < HEIGHT - 3 && self.empty_cell == [(x, y + 2), (x, y + 3)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 2, Some(e.ty));
-                        node.set(x, y + 3, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //左移
-                    if x >= 1 && self.empty_cell == [(x - 1, y), (x - 1, y + 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x - 1, y, Some(e.ty));
-                        node.set(x - 1, y + 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-
-                    //右移
-                    if x < WIDTH - 1 && self.empty_cell == [(x + 1, y), (x + 1, y + 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x + 1, y, Some(e.ty));
-                        node.set(x + 1, y + 1, Some(e.ty));
-                        node.set(x, y, None);
-                        node.set(x, y + 1, None);
-                        ret.push(node);
-                    }
-                }
-                BlockType::Pawn => {
-                    //上移一格
-                    if y >= 1 && self.empty_cell.contains(&(x, y - 1)) {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 1, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //上移二格
-                    if y >= 2 && self.empty_cell == [(x, y - 2), (x, y - 1)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y - 2, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //下移一格
-                    if y < HEIGHT - 1 && self.empty_cell.contains(&(x, y + 1)) {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 1, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //下移二格
-                    if y < HEIGHT - 2 && self.empty_cell == [(x, y + 1), (x, y + 2)] {
-                        let mut node = self.state.clone();
-                        node.set(x, y + 2, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //左移一格
-                    if x >= 1 && self.empty_cell.contains(&(x - 1, y)) {
-                        let mut node = self.state.clone();
-                        node.set(x - 1, y, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //左移二格
-                    if x >= 2 && self.empty_cell == [(x - 2, y), (x - 1, y)] {
-                        let mut node = self.state.clone();
-                        node.set(x - 2, y, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //右移一格
-                    if x < WIDTH - 1 && self.empty_cell.contains(&(x + 1, y)) {
-                        let mut node = self.state.clone();
-                        node.set(x + 1, y, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-
-                    //右移二格
-                    if x < WIDTH - 2 && self.empty_cell == [(x + 1, y), (x + 2, y)] {
-                        let mut node = self.state.clone();
-                        node.set(x + 2, y, Some(e.ty));
-                        node.set(x, y, None);
-                        ret.push(node);
-                    }
-                }
+    let start = Instant::now();
+    let solution = read_state(&args.input, args.metric).and_then(|(state, rules, roster)| {
+        let solver: Box<dyn Solver> = match (args.algorithm, args.threads) {
+            (Algorithm::Parallel, Some(threads)) => Box::new(ParallelBfs { threads, rules }),
+            (e, _) => e.solver(rules),
+        };
+        Ok((solver.solve(&state, args.limit)?, roster))
+    });
+    let (solution, mut roster) = match solution {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+
+    log::info!("{:?} explored {} nodes", args.algorithm, solution.explored);
+    match args.format {
+        Format::Text => {
+            println!("{} steps", solution.len());
+            for e in solution.moves.iter() {
+                println!("{}", roster.describe_with(e, &args.notation));
+            }
+        }
+        Format::Log => {
+            log::info!("{} steps", solution.len());
+            for e in solution.moves.iter() {
+                log::info!("{}", roster.describe_with(e, &args.notation));
+            }
+        }
+        Format::Json | Format::Csv => {
+            let report = Report {
+                solver: args.algorithm,
+                solution,
+                elapsed_ms: start.elapsed().as_millis() as u64,
+            };
+            let text = match args.format {
+                Format::Json => serde_json::to_string_pretty(&report).map_err(Into::into),
+                _ => report.to_csv(),
+            };
+            match text {
+                Ok(e) => println!("{}", e.trim_end()),
+                Err(e) => return fail(e),
             }
         }
     }
 
-    fn move_message(&self, next: &NodeValue) -> anyhow::Result<String> {
-        let next_blocks = Game::new(next)?.blocks;
-        let block0 = self
-            .blocks
-            .iter()
-            .filter(|e| !next_blocks.contains(e))
-            .collect::<Vec<_>>();
-        let block1 = next_blocks
-            .iter()
-            .filter(|e| !self.blocks.contains(e))
-            .collect::<Vec<_>>();
-
-        anyhow::ensure!(
-            block0.len() == 1 && block1.len() == 1 && block0[0].ty == block1[0].ty,
-            "2个状态无法通过一次移动完成转化"
-        );
-
-        let block0 = block0[0];
-        let block1 = block1[0];
-
-        let dx = block1.x as i32 - block0.x as i32;
-        let dy = block1.y as i32 - block0.y as i32;
-
-        let dir = match (dx, dy) {
-            (0, 1) => "下",
-            (0, 2) => "下2",
-            (0, -1) => "上",
-            (0, -2) => "上2",
-            (-1, 0) => "左",
-            (-2, 0) => "左2",
-            (1, 0) => "右",
-            (2, 0) => "右2",
-            _ => anyhow::bail!(
-                "unknown move ({},{}) => ({},{})",
-                block0.x,
-                block0.y,
-                block1.x,
-                block1.y
-            ),
+    ExitCode::SUCCESS
+}
+
+fn run_hint(args: &HintArgs) -> ExitCode {
+    utils::init_stderr_log(args.log_level);
+
+    let table = match args.tablebase.as_ref().map(Tablebase::load).transpose() {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+    let hint = read_state(&args.input, args.metric).and_then(|(state, rules, roster)| {
+        let source = match table.as_ref() {
+            Some(table) => {
+                check_rules(table, &rules)?;
+                HintSource::Tablebase(table)
+            }
+            None => HintSource::Search {
+                algorithm: args.algorithm,
+                rules,
+                limit: args.limit,
+            },
         };
+        Ok((hrd::hint(&state, source)?, roster))
+    });
+    let (hint, roster) = match hint {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+
+    println!("{} steps", hint.distance);
+    //各个移动都从当前局面出发
+    for e in hint.moves.iter() {
+        println!("{}", roster.clone().describe_with(e, &args.notation));
+    }
+    ExitCode::SUCCESS
+}
 
-        let ret = format!("({},{}) {}", block0.x, block0.y, dir);
-        return Ok(ret);
+fn run_play(args: &PlayArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
+
+    let played = read_state(&args.input, args.metric).and_then(|(state, rules, roster)| {
+        let solution = args.algorithm.solver(rules).solve(&state, args.limit)?;
+        play::play_solution(&solution, &roster, &args.notation, args.delay)
+    });
+    match played {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => fail(e),
     }
 }
 
-fn hrd_solve(state: &NodeValue, limit: usize) -> anyhow::Result<Node> {
-    let game = Game::new(state)?;
-    if game.state.is_finish() {
-        return Ok(Node {
-            val: game.state.clone(),
-            parent: None,
-        });
+fn run_game(args: &PlayArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
+
+    let played = read_state(&args.input, args.metric).and_then(|(state, rules, roster)| {
+        let options = tui::Options {
+            algorithm: args.algorithm,
+            limit: args.limit,
+            formatter: &args.notation,
+        };
+        tui::play(&mut Session::new(&state, rules)?, &roster, &options)
+    });
+    match played {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => fail(e),
     }
+}
 
-    let mut set = HashSet::new();
-    let mut list = VecDeque::new();
+fn run_tablebase_build(args: &TablebaseBuildArgs) -> ExitCode {
+    utils::init_stderr_log(args.log_level);
+
+    let table = read_state(&args.input, args.metric)
+        .and_then(|(state, rules, _)| Tablebase::build(&state, rules, args.limit));
+    let table = match table {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+    if let Err(e) = table.save(&args.output) {
+        eprintln!("write {} failed: {}", args.output, e);
+        return ExitCode::from(EXIT_BAD_INPUT);
+    }
+    println!("{} positions", table.len());
+    ExitCode::SUCCESS
+}
 
-    set.insert(game.state.clone());
-    list.push_back(Rc::new(Node {
-        val: game.state,
-        parent: None,
-    }));
+fn run_tablebase_query(args: &TablebaseQueryArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
+
+    let (table, state, roster) = match Tablebase::load(&args.table).and_then(|table| {
+        let (state, rules, roster) = read_state(&args.input, table.rules().metric)?;
+        check_rules(&table, &rules)?;
+        Ok((table, state, roster))
+    }) {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+    if !table.contains(&state) {
+        return fail(anyhow::anyhow!("position not in tablebase"));
+    }
+    match table.distance(&state) {
+        Some(distance) => {
+            println!("{} steps", distance);
+            for e in table.best_moves(&state) {
+                println!("{}", roster.clone().describe_with(&e, &args.notation));
+            }
+            ExitCode::SUCCESS
+        }
+        None => fail(SolveError::NoSolution.into()),
+    }
+}
 
-    let mut next_nodes: Vec<NodeValue> = Vec::new();
+fn run_code(args: &CodeArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
 
-    loop {
-        let node = list
-            .pop_front()
-            .ok_or_else(|| anyhow::anyhow!("can't find solve"))?;
+    let state = match read_state(&args.input, Metric::default()) {
+        Ok((state, rules, _)) if rules == Rules::default() => state,
+        Ok(_) => {
+            return fail(anyhow::anyhow!(
+                "position code cannot record custom shapes or goals"
+            ));
+        }
+        Err(e) => return fail(e),
+    };
+    println!("{}", state.encode());
+    ExitCode::SUCCESS
+}
 
-        let game = Game::new_unchecked(&node.val);
-        game.next_nodes(&mut next_nodes);
+fn run_svg(args: &SvgArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
 
-        for e in next_nodes.drain(..) {
-            if e.is_finish() {
-                return Ok(Node {
-                    val: e,
-                    parent: Some(node),
-                });
-            }
-            let entry = set.entry(e);
-            if let std::collections::hash_set::Entry::Occupied(_) = entry {
-                continue;
+    let image = read_state(&args.input, args.metric).and_then(|(state, rules, roster)| {
+        if !args.storyboard {
+            hrd::validate(&state, &rules)?;
+            return Ok(hrd::svg(&state, &rules, &roster, &args.options));
+        }
+        let solution = args.algorithm.solver(rules).solve(&state, args.limit)?;
+        hrd::storyboard(&solution, &roster, &args.notation, &args.options)
+    });
+    let image = match image {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+    match args.output.as_ref() {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, image) {
+                eprintln!("write {} failed: {}", output, e);
+                return ExitCode::from(EXIT_BAD_INPUT);
             }
-            list.push_back(Rc::new(Node {
-                val: entry.get().clone(),
-                parent: Some(Rc::clone(&node)),
-            }));
-            entry.insert();
         }
+        None => print!("{}", image),
+    }
+    ExitCode::SUCCESS
+}
 
-        if set.len() >= limit {
-            anyhow::bail!("node size exceed {}", limit);
-        }
+fn run_render(args: &RenderArgs) -> ExitCode {
+    utils::init_stderr_log(LevelFilter::Warn);
+
+    let image = read_state(&args.input, args.metric).and_then(|(state, rules, _)| {
+        let solution = args.algorithm.solver(rules).solve(&state, args.limit)?;
+        hrd::gif(&solution, &args.options)
+    });
+    let image = match image {
+        Ok(e) => e,
+        Err(e) => return fail(e),
+    };
+    if let Err(e) = std::fs::write(&args.output, image) {
+        eprintln!("write {} failed: {}", args.output, e);
+        return ExitCode::from(EXIT_BAD_INPUT);
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_serve(args: ServeArgs) -> ExitCode {
+    utils::init_stderr_log(args.log_level);
+
+    match serve::serve(args.options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => fail(e),
     }
 }
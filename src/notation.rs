@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use crate::game::{Direction, Move, Path};
+
+/// 移动说明使用的语言
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Locale {
+    /// 上2右
+    #[default]
+    ZhCn,
+    /// up 2, right
+    En,
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "zh-cn" | "zh" => Ok(Locale::ZhCn),
+            "en" => Ok(Locale::En),
+            _ => anyhow::bail!("unknown locale {}", s),
+        }
+    }
+}
+
+/// 坐标的写法, 都是先列后行, 行从上往下数
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Coords {
+    /// 从 0 开始, 如 (1,3)
+    #[default]
+    Zero,
+    /// 从 1 开始, 如 (2,4)
+    One,
+    /// 列用字母, 行从 1 开始, 如 b4
+    Chess,
+}
+
+impl FromStr for Coords {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "zero" | "0" => Ok(Coords::Zero),
+            "one" | "1" => Ok(Coords::One),
+            "chess" => Ok(Coords::Chess),
+            _ => anyhow::bail!("unknown coordinate style {}", s),
+        }
+    }
+}
+
+/// 移动说明的写法, 实现这个 trait 可以换成自己的格式
+pub trait MoveFormatter {
+    /// 棋子外接矩形左上角的位置
+    fn position(&self, pos: (usize, usize)) -> String;
+
+    /// 移动的方向和距离
+    fn path(&self, path: &Path) -> String;
+
+    /// 一步移动, 如 "(1,3) 下"
+    fn format(&self, mv: &Move) -> String {
+        format!("{} {}", self.position(mv.from), self.path(&mv.path))
+    }
+
+    /// 有名字的棋子用名字代替坐标, 如 "关羽 右"
+    fn named(&self, name: &str, mv: &Move) -> String {
+        format!("{} {}", name, self.path(&mv.path))
+    }
+}
+
+/// 内置的写法, 由语言和坐标写法组成, 默认值与 Move 的 Display 相同
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Notation {
+    pub locale: Locale,
+    pub coords: Coords,
+}
+
+impl MoveFormatter for Notation {
+    fn position(&self, (x, y): (usize, usize)) -> String {
+        match self.coords {
+            Coords::Zero => format!("({},{})", x, y),
+            Coords::One => format!("({},{})", x + 1, y + 1),
+            Coords::Chess => format!("{}{}", (b'a' + x as u8) as char, y + 1),
+        }
+    }
+
+    fn path(&self, path: &Path) -> String {
+        //连续相同的方向合并, 如 "上2右"
+        let mut segments = Vec::new();
+        let mut steps = path.iter().peekable();
+        while let Some(e) = steps.next() {
+            let mut count = 1;
+            while steps.next_if_eq(&e).is_some() {
+                count += 1;
+            }
+            segments.push(self.segment(e, count));
+        }
+        match self.locale {
+            Locale::ZhCn => segments.concat(),
+            Locale::En => segments.join(", "),
+        }
+    }
+}
+
+impl Notation {
+    /// 沿同一方向移动 count 格
+    fn segment(&self, direction: Direction, count: usize) -> String {
+        let name = match (self.locale, direction) {
+            (Locale::ZhCn, Direction::Up) => "上",
+            (Locale::ZhCn, Direction::Down) => "下",
+            (Locale::ZhCn, Direction::Left) => "左",
+            (Locale::ZhCn, Direction::Right) => "右",
+            (Locale::En, Direction::Up) => "up",
+            (Locale::En, Direction::Down) => "down",
+            (Locale::En, Direction::Left) => "left",
+            (Locale::En, Direction::Right) => "right",
+        };
+        match (self.locale, count) {
+            (_, 1) => name.to_string(),
+            (Locale::ZhCn, _) => format!("{}{}", name, count),
+            (Locale::En, _) => format!("{} {}", name, count),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::board::NodeValue;
+use crate::game::{Game, Rules};
+use crate::solve::{Solution, SolveError, Solver, check_cancel};
+
+/// 以镜像合并后的局面为键, 记录实际局面和上一个局面
+type Shard = HashMap<NodeValue, (NodeValue, Option<NodeValue>)>;
+
+/// 下一层的候选局面: (上一个局面在本层中的下标, 生成顺序, 局面), 前两项即单线程搜索时的入队顺序
+type Candidate = (usize, usize, NodeValue);
+
+/// 多线程逐层广度优先搜索
+///
+/// 每层的局面分成 threads 块交给各线程生成下一层, 再按哈希分片由各线程去重.
+/// 候选局面按单线程搜索时的入队顺序处理, 每层的局面顺序和单线程的广度优先搜索相同, 因此得到完全相同的解.
+/// 去重时每记录一个局面检查一次 limit, 达到后各线程都停止. 取消时各线程在处理下一个局面之前停止.
+#[derive(Clone, Copy, Debug)]
+pub struct ParallelBfs {
+    pub threads: usize,
+    pub rules: Rules,
+}
+
+impl Default for ParallelBfs {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |e| e.get()),
+            rules: Rules::default(),
+        }
+    }
+}
+
+/// 一个分块生成的下一层局面
+struct ChunkOutput {
+    //按分片分开, 保持生成顺序
+    shards: Vec<Vec<Candidate>>,
+    //本块中第一个到达终局的局面
+    finish: Option<Candidate>,
+}
+
+fn shard_of(key: &NodeValue, shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % shards as u64) as usize
+}
+
+/// 生成 chunk 中局面的下一层, offset 为 chunk 在本层中的起始下标, 取消时返回已生成的部分
+fn expand(
+    chunk: &[NodeValue],
+    offset: usize,
+    visited: &[Shard],
+    rules: &Rules,
+    cancel: &AtomicBool,
+) -> ChunkOutput {
+    let mut shards = vec![Vec::new(); visited.len()];
+    let mut next_nodes = Vec::new();
+    for (i, e) in chunk.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        Game::new_unchecked(e, rules).next_nodes(&mut next_nodes);
+        for (j, (_, n)) in next_nodes.drain(..).enumerate() {
+            if rules.is_finish(&n) {
+                return ChunkOutput {
+                    shards,
+                    finish: Some((offset + i, j, n)),
+                };
+            }
+            let key = rules.key(&n);
+            let shard = shard_of(&key, visited.len());
+            if !visited[shard].contains_key(&key) {
+                shards[shard].push((offset + i, j, n));
+            }
+        }
+    }
+    ChunkOutput {
+        shards,
+        finish: None,
+    }
+}
+
+/// 所有分片共用的局面计数
+struct Counter<'a> {
+    explored: AtomicUsize,
+    limit: usize,
+    //已达到 limit
+    exceeded: AtomicBool,
+    cancel: &'a AtomicBool,
+}
+
+/// 按顺序处理一个分片的候选局面, 只保留每个局面第一次出现
+fn dedup(
+    shard: usize,
+    visited: &mut Shard,
+    outputs: &[ChunkOutput],
+    frontier: &[NodeValue],
+    rules: &Rules,
+    counter: &Counter,
+) -> Vec<Candidate> {
+    let mut ret = Vec::new();
+    for output in outputs.iter() {
+        for &(parent, order, e) in output.shards[shard].iter() {
+            if counter.exceeded.load(Ordering::Relaxed) || counter.cancel.load(Ordering::Relaxed) {
+                return ret;
+            }
+            let key = rules.key(&e);
+            if visited.contains_key(&key) {
+                continue;
+            }
+            visited.insert(key, (e, Some(frontier[parent])));
+            ret.push((parent, order, e));
+            if counter.explored.fetch_add(1, Ordering::Relaxed) + 1 >= counter.limit {
+                counter.exceeded.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+    ret
+}
+
+impl Solver for ParallelBfs {
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution> {
+        Game::new(state, &self.rules)?;
+        if self.rules.is_finish(state) {
+            return Solution::from_states(&[*state], &self.rules, 1);
+        }
+
+        let threads = self.threads.max(1);
+        let rules = &self.rules;
+        let mut visited: Vec<Shard> = (0..threads).map(|_| Shard::new()).collect();
+        let key = rules.key(state);
+        visited[shard_of(&key, threads)].insert(key, (*state, None));
+
+        let counter = Counter {
+            explored: AtomicUsize::new(1),
+            limit,
+            exceeded: AtomicBool::new(limit <= 1),
+            cancel,
+        };
+        let mut frontier = vec![*state];
+        loop {
+            if frontier.is_empty() {
+                return Err(SolveError::NoSolution.into());
+            }
+
+            //每个线程一块
+            let chunk_size = frontier.len().div_ceil(threads);
+            let outputs: Vec<ChunkOutput> = thread::scope(|s| {
+                let visited = &visited;
+                let handles: Vec<_> = frontier
+                    .chunks(chunk_size)
+                    .enumerate()
+                    .map(|(i, chunk)| {
+                        s.spawn(move || expand(chunk, i * chunk_size, visited, rules, cancel))
+                    })
+                    .collect();
+                handles.into_iter().map(|e| e.join().unwrap()).collect()
+            });
+            check_cancel(cancel)?;
+
+            //分块按顺序排列, 第一个找到的终局就是单线程搜索找到的终局
+            if let Some((parent, _, finish)) = outputs.iter().find_map(|e| e.finish) {
+                let explored = visited.iter().map(|e| e.len()).sum();
+                let mut states = vec![finish];
+                let mut current = Some(frontier[parent]);
+                while let Some(e) = current {
+                    states.push(e);
+                    let key = rules.key(&e);
+                    current = visited[shard_of(&key, threads)][&key].1;
+                }
+                states.reverse();
+                return Solution::from_states(&states, rules, explored);
+            }
+
+            let mut next: Vec<Candidate> = thread::scope(|s| {
+                let (outputs, frontier, counter) = (&outputs, &frontier, &counter);
+                let handles: Vec<_> = visited
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(shard, visited)| {
+                        s.spawn(move || dedup(shard, visited, outputs, frontier, rules, counter))
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|e| e.join().unwrap())
+                    .collect()
+            });
+            check_cancel(cancel)?;
+            if counter.exceeded.load(Ordering::Relaxed) {
+                return Err(SolveError::LimitExceeded(limit).into());
+            }
+            next.sort_unstable_by_key(|e| (e.0, e.1));
+            frontier = next.into_iter().map(|e| e.2).collect();
+        }
+    }
+}
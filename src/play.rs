@@ -0,0 +1,265 @@
+//! 终端中的棋盘绘制和解的回放
+
+use std::{
+    io::{IsTerminal, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use hrd::{Block, BlockType, MoveFormatter, NodeValue, Roster, Rules, Solution};
+
+//每格在终端中占 4 列 2 行, 格子之间留 1 列或 1 行的缝
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 2;
+
+/// 回放时最快和最慢的每步间隔
+const MIN_DELAY: Duration = Duration::from_millis(50);
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// 画出局面, 每个棋子一种底色, 标出的棋子用亮色, 格子中是棋子的字母或者名字的字
+///
+/// 返回的各行不带换行符, 可以直接输出.
+pub fn draw_board(
+    state: &NodeValue,
+    rules: &Rules,
+    roster: &Roster,
+    highlight: Option<Block>,
+) -> Vec<String> {
+    let (width, height) = state.size();
+    let blocks = state.blocks(rules);
+    //每格属于哪个棋子
+    let mut owner = vec![None; width * height];
+    for (i, e) in blocks.iter().enumerate() {
+        let shape = rules.shapes.shape(e.ty);
+        for (x, y) in shape
+            .place((e.x, e.y), (width, height))
+            .into_iter()
+            .flatten()
+        {
+            owner[y * width + x] = Some(i);
+        }
+    }
+    let owner_at = |x: usize, y: usize| owner[y * width + x];
+
+    let cols = width * (CELL_WIDTH + 1) - 1;
+    let rows = height * (CELL_HEIGHT + 1) - 1;
+    let mut ret = Vec::with_capacity(rows + 2);
+    ret.push(format!("┌{}┐", "─".repeat(cols)));
+    for dy in 0..rows {
+        let (y, gap_y) = (
+            dy / (CELL_HEIGHT + 1),
+            dy % (CELL_HEIGHT + 1) == CELL_HEIGHT,
+        );
+        let mut line = String::from("│");
+        let mut dx = 0;
+        while dx < cols {
+            let (x, gap_x) = (dx / (CELL_WIDTH + 1), dx % (CELL_WIDTH + 1) == CELL_WIDTH);
+            //缝两边是同一个棋子时填上底色
+            let piece = match (gap_x, gap_y) {
+                (false, false) => owner_at(x, y),
+                (true, false) => owner_at(x, y).filter(|e| owner_at(x + 1, y) == Some(*e)),
+                (false, true) => owner_at(x, y).filter(|e| owner_at(x, y + 1) == Some(*e)),
+                (true, true) => owner_at(x, y).filter(|e| {
+                    [(x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                        .iter()
+                        .all(|&(x, y)| owner_at(x, y) == Some(*e))
+                }),
+            };
+            let Some(piece) = piece else {
+                line.push(' ');
+                dx += 1;
+                continue;
+            };
+
+            let block = blocks[piece];
+            let bright = highlight == Some(block);
+            let first = rules.shapes.shape(block.ty).first();
+            //标签放在棋子第一格的第一行
+            let text = if !gap_x
+                && !gap_y
+                && (x, y) == (block.x + first.0, block.y + first.1)
+                && dy % (CELL_HEIGHT + 1) == 0
+            {
+                let label = roster
+                    .get(&block)
+                    .map(|e| e.letter)
+                    .or_else(|| rules.shapes.letter(block.ty))
+                    .unwrap_or('?');
+                //非 ASCII 的字在终端中占 2 列
+                let pad = if label.is_ascii() { 2 } else { 1 };
+                dx += CELL_WIDTH;
+                format!(" {}{}", label, " ".repeat(pad))
+            } else if gap_x {
+                dx += 1;
+                " ".to_string()
+            } else {
+                let len = CELL_WIDTH - dx % (CELL_WIDTH + 1);
+                dx += len;
+                " ".repeat(len)
+            };
+            line.push_str(&format!(
+                "\x1b[{};30m{}\x1b[0m",
+                color(block.ty, bright),
+                text
+            ));
+        }
+        line.push('│');
+        ret.push(line);
+    }
+    ret.push(format!("└{}┘", "─".repeat(cols)));
+    ret
+}
+
+/// 棋子类型的 ANSI 底色, bright 时用亮色
+fn color(ty: BlockType, bright: bool) -> u8 {
+    let base = match ty {
+        BlockType::CaoCao => 41,
+        BlockType::Horizontal => 44,
+        BlockType::Vertical => 42,
+        BlockType::Pawn => 43,
+        BlockType::Custom(code) => 40 + code,
+    };
+    if bright { base + 60 } else { base }
+}
+
+/// 回放时的按键
+enum Key {
+    Pause,
+    Back,
+    Forward,
+    Faster,
+    Slower,
+    Quit,
+}
+
+/// 等待按键, timeout 为 None 时一直等待, 超时返回 None
+fn wait_key(timeout: Option<Duration>) -> anyhow::Result<Option<Key>> {
+    loop {
+        if let Some(timeout) = timeout
+            && !event::poll(timeout)?
+        {
+            return Ok(None);
+        }
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        let key = match code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => Key::Pause,
+            KeyCode::Left | KeyCode::Char('b') | KeyCode::Backspace => Key::Back,
+            KeyCode::Right | KeyCode::Char('n') => Key::Forward,
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => Key::Faster,
+            KeyCode::Char('-') | KeyCode::Down => Key::Slower,
+            KeyCode::Char('q') | KeyCode::Esc => Key::Quit,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+            _ => continue,
+        };
+        return Ok(Some(key));
+    }
+}
+
+/// 退出时恢复终端
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        print!("\x1b[?25h");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// 逐步回放解, 每步原地重画棋盘并标出刚移动的棋子
+///
+/// 空格暂停, 左右方向键后退和前进一步, +/- 调整速度, q 退出.
+/// 不在终端中运行时依次输出每一步, 不响应按键.
+pub fn play_solution(
+    solution: &Solution,
+    roster: &Roster,
+    formatter: &dyn MoveFormatter,
+    mut delay: Duration,
+) -> anyhow::Result<()> {
+    let positions = solution.positions()?;
+    let interactive = std::io::stdout().is_terminal() && terminal::enable_raw_mode().is_ok();
+    //非交互时不构造, 否则析构时也会输出恢复光标的转义
+    let _guard = if interactive { Some(RawMode) } else { None };
+    let newline = if interactive { "\r\n" } else { "\n" };
+
+    let mut stdout = std::io::stdout();
+    let (mut step, mut paused, mut drawn) = (0, false, 0);
+    loop {
+        //重新计算标出的棋子到这一步的位置
+        let mut names = roster.clone();
+        let mut text = String::from("初始局面");
+        for e in solution.moves[..step].iter() {
+            text = names.describe_with(e, formatter);
+        }
+        let highlight = step.checked_sub(1).map(|i| {
+            let mv = &solution.moves[i];
+            let (x, y) = mv.to();
+            Block { ty: mv.piece, x, y }
+        });
+
+        let mut frame = String::new();
+        if interactive && drawn > 0 {
+            //回到上一帧的开头
+            frame.push_str(&format!("\x1b[{}A\r\x1b[J", drawn));
+        }
+        let mut lines = vec![format!("{}/{}  {}", step, solution.len(), text)];
+        lines.extend(draw_board(
+            &positions[step],
+            &solution.rules,
+            &names,
+            highlight,
+        ));
+        if interactive {
+            let state = if paused { "暂停" } else { "播放" };
+            lines.push(format!(
+                "{} {}ms/步  空格 暂停  ←/→ 后退/前进  +/- 速度  q 退出",
+                state,
+                delay.as_millis()
+            ));
+        }
+        drawn = lines.len();
+        for e in lines {
+            frame.push_str(&e);
+            frame.push_str(newline);
+        }
+        if interactive {
+            frame.insert_str(0, "\x1b[?25l");
+        }
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+
+        if !interactive {
+            if step == solution.len() {
+                return Ok(());
+            }
+            std::thread::sleep(delay);
+            step += 1;
+            continue;
+        }
+
+        let timeout = (!paused && step < solution.len()).then_some(delay);
+        match wait_key(timeout)? {
+            None => step += 1,
+            Some(Key::Pause) => paused = !paused,
+            Some(Key::Back) => {
+                step = step.saturating_sub(1);
+                paused = true;
+            }
+            Some(Key::Forward) => step = (step + 1).min(solution.len()),
+            Some(Key::Faster) => delay = (delay / 2).max(MIN_DELAY),
+            Some(Key::Slower) => delay = (delay * 2).min(MAX_DELAY),
+            Some(Key::Quit) => return Ok(()),
+        }
+    }
+}
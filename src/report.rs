@@ -0,0 +1,265 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{NodeValue, Shape, Shapes};
+use crate::game::{Direction, Metric, Move, Path, Rules};
+use crate::goal::Goal;
+use crate::hint::Hint;
+use crate::solve::{Algorithm, Solution};
+
+/// 一次求解的完整结果, 可以输出为 JSON 或 CSV
+///
+/// JSON 中局面用局面码表示, 棋子用字母表示, 读回后与原值相同:
+///
+/// ```json
+/// {"solver": "bfs", "metric": "slide", "start": "45LZLZTajcEg", "goal": "exit",
+///  "steps": 90, "explored": 12345, "elapsed_ms": 20,
+///  "moves": [{"piece": "v", "from": [1, 3], "to": [1, 4], "direction": "down", "distance": 1}, ...]}
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(into = "ReportRecord", try_from = "ReportRecord")]
+pub struct Report {
+    pub solver: Algorithm,
+    pub solution: Solution,
+    /// 求解耗时, 毫秒
+    pub elapsed_ms: u64,
+}
+
+impl Report {
+    /// 每步一行的 CSV, 第 0 行只有初始局面, position 为该步之后的局面码
+    pub fn to_csv(&self) -> anyhow::Result<String> {
+        let shapes = &self.solution.rules.shapes;
+        let positions = self.solution.positions()?;
+        let mut ret =
+            String::from("step,piece,from_x,from_y,to_x,to_y,direction,distance,position\n");
+        writeln!(ret, "0,,,,,,,,{}", positions[0].encode())?;
+        for (i, e) in self.solution.moves.iter().enumerate() {
+            let e = MoveRecord::new(e, shapes);
+            writeln!(
+                ret,
+                "{},{},{},{},{},{},{},{},{}",
+                i + 1,
+                e.piece,
+                e.from.0,
+                e.from.1,
+                e.to.0,
+                e.to.1,
+                e.direction,
+                e.distance,
+                positions[i + 1].encode()
+            )?;
+        }
+        Ok(ret)
+    }
+}
+
+/// 提示的 JSON 形式, 移动的写法与 Report 相同
+///
+/// ```json
+/// {"distance": 81, "moves": [{"piece": "p", "from": [1, 4], "to": [0, 4], "direction": "left", "distance": 1}]}
+/// ```
+#[derive(Serialize)]
+pub struct HintReport {
+    distance: usize,
+    moves: Vec<MoveRecord>,
+}
+
+impl HintReport {
+    /// shapes 用来给出棋子的字母
+    pub fn new(hint: &Hint, shapes: &Shapes) -> Self {
+        Self {
+            distance: hint.distance,
+            moves: hint
+                .moves
+                .iter()
+                .map(|e| MoveRecord::new(e, shapes))
+                .collect(),
+        }
+    }
+}
+
+//JSON 中的形式
+#[derive(Serialize, Deserialize)]
+struct ReportRecord {
+    solver: String,
+    metric: String,
+    start: String,
+    //按定义顺序, 与局面码中的格子编码对应
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    shapes: Vec<ShapeRecord>,
+    goal: String,
+    steps: usize,
+    explored: usize,
+    elapsed_ms: u64,
+    moves: Vec<MoveRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShapeRecord {
+    letter: char,
+    mask: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoveRecord {
+    piece: char,
+    from: (usize, usize),
+    to: (usize, usize),
+    //直线移动时为一个方向, 拐弯时为每一格的方向, 用空格分隔
+    direction: String,
+    distance: usize,
+}
+
+impl MoveRecord {
+    fn new(mv: &Move, shapes: &Shapes) -> Self {
+        let mut dirs = mv.path.iter().map(direction_name);
+        let direction = if mv.path.is_straight() {
+            dirs.next().unwrap_or_default().to_string()
+        } else {
+            dirs.collect::<Vec<_>>().join(" ")
+        };
+        Self {
+            piece: shapes.letter(mv.piece).unwrap_or('?'),
+            from: mv.from,
+            to: mv.to(),
+            direction,
+            distance: mv.path.len(),
+        }
+    }
+
+    fn to_move(&self, shapes: &Shapes) -> anyhow::Result<Move> {
+        let piece = shapes
+            .ty(self.piece)
+            .ok_or_else(|| anyhow::anyhow!("unknown piece {}", self.piece))?;
+        let dirs = self
+            .direction
+            .split_whitespace()
+            .map(|e| {
+                Direction::ALL
+                    .into_iter()
+                    .find(|d| direction_name(*d) == e)
+                    .ok_or_else(|| anyhow::anyhow!("unknown direction {}", e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(
+            self.distance <= Path::MAX_LEN,
+            "distance {} exceeds {}",
+            self.distance,
+            Path::MAX_LEN
+        );
+        let path = match dirs[..] {
+            [e] => Path::straight(e, self.distance),
+            _ => {
+                anyhow::ensure!(
+                    dirs.len() == self.distance,
+                    "direction {} mismatch distance {}",
+                    self.direction,
+                    self.distance
+                );
+                let mut path = Path::default();
+                dirs.into_iter().for_each(|e| path.push(e));
+                path
+            }
+        };
+        let ret = Move {
+            piece,
+            from: self.from,
+            path,
+        };
+        anyhow::ensure!(
+            ret.to() == self.to,
+            "move from ({},{}) {} ends at {:?}, not ({},{})",
+            self.from.0,
+            self.from.1,
+            self.direction,
+            ret.to(),
+            self.to.0,
+            self.to.1
+        );
+        Ok(ret)
+    }
+}
+
+fn direction_name(e: Direction) -> &'static str {
+    match e {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+impl From<Report> for ReportRecord {
+    fn from(report: Report) -> Self {
+        let Solution {
+            start,
+            moves,
+            rules,
+            explored,
+        } = report.solution;
+        Self {
+            solver: report.solver.to_string(),
+            metric: rules.metric.to_string(),
+            start: start.encode(),
+            shapes: rules
+                .shapes
+                .iter()
+                .map(|(_, letter, shape)| ShapeRecord {
+                    letter,
+                    mask: shape.to_string(),
+                })
+                .collect(),
+            goal: rules.goal.to_text(&rules.shapes),
+            steps: moves.len(),
+            explored,
+            elapsed_ms: report.elapsed_ms,
+            moves: moves
+                .iter()
+                .map(|e| MoveRecord::new(e, &rules.shapes))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ReportRecord> for Report {
+    type Error = anyhow::Error;
+
+    fn try_from(record: ReportRecord) -> anyhow::Result<Self> {
+        let start = NodeValue::decode(&record.start)?;
+        let mut shapes = Shapes::default();
+        for e in record.shapes.iter() {
+            shapes.define(e.letter, Shape::parse(&e.mask)?)?;
+        }
+        let rules = Rules {
+            metric: record.metric.parse::<Metric>()?,
+            shapes,
+            goal: Goal::from_text(&record.goal, &shapes, start.size())?,
+        };
+        let moves = record
+            .moves
+            .iter()
+            .map(|e| e.to_move(&shapes))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(
+            moves.len() == record.steps,
+            "steps {} mismatch {} moves",
+            record.steps,
+            moves.len()
+        );
+
+        let solution = Solution {
+            start,
+            moves,
+            rules,
+            explored: record.explored,
+        };
+        //每一步都必须合法
+        solution.positions()?;
+        Ok(Self {
+            solver: record.solver.parse()?,
+            solution,
+            elapsed_ms: record.elapsed_ms,
+        })
+    }
+}
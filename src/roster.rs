@@ -0,0 +1,52 @@
+use crate::board::Block;
+use crate::game::Move;
+use crate::notation::{MoveFormatter, Notation};
+
+/// 输入中用独立字母标出或者起了名字的棋子
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NamedPiece {
+    pub letter: char,
+    pub name: Option<String>,
+    /// 当前的位置
+    pub block: Block,
+}
+
+/// 单独标出的棋子, 随移动更新位置, 用来在解的每一步认出是哪个棋子
+///
+/// 搜索时同形状的棋子仍然视为相同, 只在输出时区分.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Roster {
+    pub pieces: Vec<NamedPiece>,
+}
+
+impl Roster {
+    /// 当前位于 block 的棋子
+    pub fn get(&self, block: &Block) -> Option<&NamedPiece> {
+        self.pieces.iter().find(|e| e.block == *block)
+    }
+
+    /// 执行一步移动, 返回移动的棋子, 移动的不是单独标出的棋子时为 None
+    pub fn apply(&mut self, mv: &Move) -> Option<&NamedPiece> {
+        let block = Block {
+            ty: mv.piece,
+            x: mv.from.0,
+            y: mv.from.1,
+        };
+        let e = self.pieces.iter_mut().find(|e| e.block == block)?;
+        (e.block.x, e.block.y) = mv.to();
+        Some(e)
+    }
+
+    /// 执行一步移动并给出说明, 有名字的棋子用名字代替坐标, 如 "关羽 右"
+    pub fn describe(&mut self, mv: &Move) -> String {
+        self.describe_with(mv, &Notation::default())
+    }
+
+    /// 同 describe, 按 formatter 的写法给出说明
+    pub fn describe_with(&mut self, mv: &Move, formatter: &dyn MoveFormatter) -> String {
+        match self.apply(mv).and_then(|e| e.name.as_ref()) {
+            Some(name) => formatter.named(name, mv),
+            None => formatter.format(mv),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+//! 本机的 HTTP JSON 求解服务
+//!
+//! 所有接口都是 POST, 请求体是 JSON, board 为局面文本或者局面码:
+//!
+//! - /solve 求解, 返回与 -f json 相同的结果
+//! - /hint 剩余步数和下一步
+//! - /validate 检查局面, 返回所有问题
+//! - /render 画出局面或者解, format 为 svg, storyboard 或 gif, 返回图片
+//!
+//! 出错时返回 {"error": 错误类型, "message": 说明}, 局面有问题时还有 problems.
+//! 超时后取消搜索, 归还名额.
+
+use std::{
+    io::Read,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use hrd::{
+    Algorithm, GifOptions, Hint, HintReport, Metric, Notation, ParallelBfs, Problem, Report, Rules,
+    SolveError, Solver, SvgOptions, ValidationError,
+};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::parse_board;
+
+/// 服务的设置
+#[derive(Debug)]
+pub struct Options {
+    /// 监听的端口, 只接受本机的连接
+    pub port: u16,
+    /// 同时处理的请求数, 也是同时运行的搜索数
+    pub workers: usize,
+    /// 每个请求搜索局面数量的上限, 请求中的 limit 不能超过它
+    pub limit: usize,
+    /// 每个请求的最长时间, 请求中的 timeout_ms 不能超过它
+    pub timeout: Duration,
+}
+
+//请求体的最大字节数
+const MAX_BODY: usize = 64 * 1024;
+//render 的分镜每行局面数的上限, cell 和 tweens 的上限与 GIF 相同
+const MAX_COLUMNS: usize = 50;
+
+/// 请求体, 除 board 外都可以省略
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Params {
+    board: String,
+    algorithm: Option<String>,
+    metric: Option<String>,
+    limit: Option<usize>,
+    timeout_ms: Option<u64>,
+    //以下只用于 render
+    format: Option<String>,
+    cell: Option<u16>,
+    labels: Option<bool>,
+    columns: Option<usize>,
+    delay_ms: Option<u16>,
+    tweens: Option<u16>,
+    lang: Option<String>,
+    coords: Option<String>,
+}
+
+/// 出错时的回复
+#[derive(Serialize)]
+struct Failure {
+    #[serde(skip)]
+    status: u16,
+    error: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    problems: Vec<Problem>,
+}
+
+impl Failure {
+    fn new(status: u16, error: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            error,
+            message: message.to_string(),
+            problems: Vec::new(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<SolveError>() {
+            Ok(SolveError::NoSolution) => Self::new(422, "no_solution", SolveError::NoSolution),
+            Ok(e @ SolveError::LimitExceeded(_)) => Self::new(422, "limit_exceeded", e),
+            Ok(e @ SolveError::Cancelled) => Self::new(504, "timeout", e),
+            Err(e) => match e.downcast::<ValidationError>() {
+                Ok(e) => Self {
+                    problems: e.problems.clone(),
+                    ..Self::new(400, "invalid_board", e)
+                },
+                Err(e) => Self::new(400, "bad_request", e),
+            },
+        }
+    }
+}
+
+/// 成功时的回复
+struct Reply {
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(val: &T) -> Result<Self, Failure> {
+        Ok(Self {
+            content_type: "application/json",
+            body: serde_json::to_vec(val).map_err(|e| Failure::new(500, "internal", e))?,
+        })
+    }
+}
+
+/// 在 127.0.0.1 上监听, 由 workers 个线程处理请求, 不会返回
+pub fn serve(options: Options) -> anyhow::Result<()> {
+    let server = Server::http(("127.0.0.1", options.port))
+        .map_err(|e| anyhow::anyhow!("listen on port {} failed: {}", options.port, e))?;
+    log::info!("listening on http://{}", server.server_addr());
+    let server = Arc::new(server);
+    let options = Arc::new(options);
+    //正在运行的搜索, 包括已超时但还没结束的
+    let running = Arc::new(AtomicUsize::new(0));
+    let workers = (0..options.workers.max(1))
+        .map(|_| {
+            let (server, options, running) = (server.clone(), options.clone(), running.clone());
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, &options, &running);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for e in workers {
+        let _ = e.join();
+    }
+    Ok(())
+}
+
+fn handle(mut request: Request, options: &Options, running: &Arc<AtomicUsize>) {
+    let start = Instant::now();
+    let (status, content_type, body) = match route(&mut request, options, running) {
+        Ok(e) => (200, e.content_type, e.body),
+        Err(e) => (
+            e.status,
+            "application/json",
+            serde_json::to_vec(&e).unwrap_or_default(),
+        ),
+    };
+    log::info!(
+        "{} {} {} {}ms",
+        request.method(),
+        request.url(),
+        status,
+        start.elapsed().as_millis()
+    );
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        log::warn!("respond failed: {}", e);
+    }
+}
+
+fn route(
+    request: &mut Request,
+    options: &Options,
+    running: &Arc<AtomicUsize>,
+) -> Result<Reply, Failure> {
+    let endpoint = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if !["/solve", "/hint", "/validate", "/render"].contains(&endpoint.as_str()) {
+        return Err(Failure::new(
+            404,
+            "not_found",
+            format!("unknown endpoint {}", endpoint),
+        ));
+    }
+    if *request.method() != Method::Post {
+        return Err(Failure::new(405, "method_not_allowed", "use POST"));
+    }
+    if request.body_length().is_some_and(|e| e > MAX_BODY) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| Failure::new(400, "bad_request", e))?;
+    if body.len() > MAX_BODY {
+        return Err(too_large());
+    }
+    let params: Params =
+        serde_json::from_slice(&body).map_err(|e| Failure::new(400, "bad_request", e))?;
+    check_bounds(&params)?;
+
+    let metric = match params.metric.as_deref() {
+        Some(e) => e.parse()?,
+        None => Metric::default(),
+    };
+    let algorithm: Algorithm = match params.algorithm.as_deref() {
+        Some(e) => e.parse()?,
+        None => Algorithm::default(),
+    };
+    let limit = params.limit.unwrap_or(options.limit).min(options.limit);
+    let timeout = params
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(options.timeout)
+        .min(options.timeout);
+    let job = Job {
+        timeout,
+        workers: options.workers.max(1),
+        running,
+    };
+
+    if endpoint == "/validate" {
+        return validate(&params.board, metric);
+    }
+    let (state, rules, roster) = parse_board(&params.board, metric)?;
+    match endpoint.as_str() {
+        "/solve" => {
+            let solver = job.solver(algorithm, rules);
+            let report = job.run(move |cancel| {
+                let start = Instant::now();
+                let solution = solver.solve_cancellable(&state, limit, cancel)?;
+                Ok(Report {
+                    solver: algorithm,
+                    solution,
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                })
+            })?;
+            Reply::json(&report)
+        }
+        "/hint" => {
+            let solver = job.solver(algorithm, rules);
+            let hint: Hint =
+                job.run(move |cancel| Ok(solver.solve_cancellable(&state, limit, cancel)?.into()))?;
+            Reply::json(&HintReport::new(&hint, &rules.shapes))
+        }
+        _ => {
+            let mut notation = Notation::default();
+            if let Some(e) = params.lang.as_deref() {
+                notation.locale = e.parse()?;
+            }
+            if let Some(e) = params.coords.as_deref() {
+                notation.coords = e.parse()?;
+            }
+            let defaults = SvgOptions::default();
+            let svg_options = SvgOptions {
+                cell: params.cell.map_or(defaults.cell, u32::from),
+                labels: params.labels.unwrap_or(defaults.labels),
+                columns: params.columns.unwrap_or(defaults.columns),
+            };
+            let defaults = GifOptions::default();
+            let gif_options = GifOptions {
+                cell: params.cell.unwrap_or(defaults.cell),
+                //GIF 的时间单位是 1/100 秒
+                delay: params.delay_ms.map_or(defaults.delay, |e| e.div_ceil(10)),
+                tweens: params.tweens.unwrap_or(defaults.tweens),
+            };
+            match params.format.as_deref().unwrap_or("svg") {
+                "svg" => {
+                    hrd::validate(&state, &rules).map_err(anyhow::Error::from)?;
+                    Ok(Reply {
+                        content_type: "image/svg+xml",
+                        body: hrd::svg(&state, &rules, &roster, &svg_options).into_bytes(),
+                    })
+                }
+                "storyboard" => {
+                    let solver = job.solver(algorithm, rules);
+                    let image = job.run(move |cancel| {
+                        let solution = solver.solve_cancellable(&state, limit, cancel)?;
+                        hrd::storyboard(&solution, &roster, &notation, &svg_options)
+                    })?;
+                    Ok(Reply {
+                        content_type: "image/svg+xml",
+                        body: image.into_bytes(),
+                    })
+                }
+                "gif" => {
+                    let solver = job.solver(algorithm, rules);
+                    let image = job.run(move |cancel| {
+                        let solution = solver.solve_cancellable(&state, limit, cancel)?;
+                        hrd::gif(&solution, &gif_options)
+                    })?;
+                    Ok(Reply {
+                        content_type: "image/gif",
+                        body: image,
+                    })
+                }
+                e => Err(Failure::new(
+                    400,
+                    "bad_request",
+                    format!("unknown format {}", e),
+                )),
+            }
+        }
+    }
+}
+
+/// 图片参数超过上限时拒绝, 避免一个请求占用过多内存
+fn check_bounds(params: &Params) -> Result<(), Failure> {
+    let bounds = [
+        (
+            "cell",
+            params.cell.map(usize::from),
+            GifOptions::MAX_CELL as usize,
+        ),
+        ("columns", params.columns, MAX_COLUMNS),
+        (
+            "tweens",
+            params.tweens.map(usize::from),
+            GifOptions::MAX_TWEENS as usize,
+        ),
+    ];
+    for (name, val, max) in bounds {
+        if let Some(val) = val.filter(|e| *e > max) {
+            return Err(Failure::new(
+                400,
+                "bad_request",
+                format!("{} {} exceeds {}", name, val, max),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn too_large() -> Failure {
+    Failure::new(
+        413,
+        "too_large",
+        format!("request body exceeds {} bytes", MAX_BODY),
+    )
+}
+
+/// 局面有问题时也是正常的回复, valid 为 false
+fn validate(board: &str, metric: Metric) -> Result<Reply, Failure> {
+    #[derive(Serialize)]
+    struct Validation {
+        valid: bool,
+        problems: Vec<Problem>,
+    }
+    let problems = match parse_board(board, metric) {
+        Ok(_) => Vec::new(),
+        Err(e) => match e.downcast::<ValidationError>() {
+            Ok(e) => e.problems,
+            Err(e) => vec![Problem {
+                pos: None,
+                message: e.to_string(),
+            }],
+        },
+    };
+    Reply::json(&Validation {
+        valid: problems.is_empty(),
+        problems,
+    })
+}
+
+/// 在单独的线程中运行搜索, 超时后取消
+///
+/// 被取消的搜索在扩展下一个局面之前停止, 之后才归还名额, 同时运行的搜索达到 workers 时拒绝新的请求.
+struct Job<'a> {
+    timeout: Duration,
+    workers: usize,
+    running: &'a Arc<AtomicUsize>,
+}
+
+/// 搜索线程结束时归还名额, 搜索 panic 时也会归还
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Job<'_> {
+    /// 求解器, 并行搜索由各个请求平分 CPU 核心
+    fn solver(&self, algorithm: Algorithm, rules: Rules) -> Box<dyn Solver + Send + Sync> {
+        match algorithm {
+            Algorithm::Parallel => Box::new(ParallelBfs {
+                threads: (ParallelBfs::default().threads / self.workers).max(1),
+                rules,
+            }),
+            _ => algorithm.solver(rules),
+        }
+    }
+
+    /// 运行 f, 超时后设置传给 f 的取消标记
+    fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&AtomicBool) -> anyhow::Result<T> + Send + 'static,
+    ) -> Result<T, Failure> {
+        if self.running.fetch_add(1, Ordering::SeqCst) >= self.workers {
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            return Err(Failure::new(503, "busy", "too many searches running"));
+        }
+        let slot = Slot(self.running.clone());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                let result = f(&cancel);
+                //先归还名额再回复, 收到结果后立即发出的请求不会被拒绝
+                drop(slot);
+                let _ = tx.send(result);
+            });
+        }
+        match rx.recv_timeout(self.timeout) {
+            Ok(e) => Ok(e?),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                cancel.store(true, Ordering::Relaxed);
+                Err(Failure::new(
+                    504,
+                    "timeout",
+                    format!(
+                        "no result within {}ms, the search is cancelled",
+                        self.timeout.as_millis()
+                    ),
+                ))
+            }
+            //搜索线程 panic, 没有发送结果
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(Failure::new(500, "internal", "search failed"))
+            }
+        }
+    }
+}
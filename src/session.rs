@@ -0,0 +1,135 @@
+use crate::board::{Block, NodeValue};
+use crate::game::{Direction, Game, Move, Rules};
+use crate::roster::Roster;
+
+/// 一局游戏: 从初始局面开始逐格移动棋子, 可以无限撤销和重做
+///
+/// 按 rules 的计步方式合并移动: slide 时同一棋子沿直线继续移动, piece 时同一棋子继续移动, 都不增加步数.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub rules: Rules,
+    start: NodeValue,
+    //每一步移动和移动后的局面
+    history: Vec<(Move, NodeValue)>,
+    //撤销的步, 最后撤销的在末尾
+    undone: Vec<(Move, NodeValue)>,
+}
+
+impl Session {
+    /// 检查局面后开始游戏, 图案终局允许没有棋子的局面, 但这样的局面无法游戏
+    pub fn new(state: &NodeValue, rules: Rules) -> anyhow::Result<Self> {
+        let game = Game::new(state, &rules)?;
+        anyhow::ensure!(!game.blocks.is_empty(), "there are no pieces to move");
+        Ok(Self {
+            rules,
+            start: *state,
+            history: Vec::new(),
+            undone: Vec::new(),
+        })
+    }
+
+    pub fn start(&self) -> &NodeValue {
+        &self.start
+    }
+
+    /// 当前局面
+    pub fn state(&self) -> &NodeValue {
+        self.history.last().map_or(&self.start, |e| &e.1)
+    }
+
+    /// 已走的步, 按计步方式合并后的移动
+    pub fn moves(&self) -> impl Iterator<Item = &Move> {
+        self.history.iter().map(|e| &e.0)
+    }
+
+    /// 步数
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// 是否已到达终局
+    pub fn is_finish(&self) -> bool {
+        self.rules.is_finish(self.state())
+    }
+
+    /// 当前局面上的棋子
+    pub fn blocks(&self) -> Vec<Block> {
+        self.state().blocks(&self.rules)
+    }
+
+    /// 当前局面上的所有合法移动
+    pub fn legal_moves(&self) -> Vec<(Move, NodeValue)> {
+        self.state().moves(&self.rules)
+    }
+
+    /// 把 block 沿 direction 移动一格, 不合法时返回错误, 返回移动后棋子的位置
+    ///
+    /// 能与上一步合并时合并, 回到上一步之前的局面时去掉上一步. 新的移动会清空重做记录.
+    pub fn step(&mut self, block: Block, direction: Direction) -> anyhow::Result<Block> {
+        let illegal = || anyhow::anyhow!("({},{}) can't move {:?}", block.x, block.y, direction);
+        let (x, y) = direction.step((block.x, block.y)).ok_or_else(illegal)?;
+        let to = Block { x, y, ..block };
+        //合法性以生成的移动为准
+        let next = self
+            .legal_moves()
+            .into_iter()
+            .find(|(mv, _)| {
+                mv.piece == block.ty && mv.from == (block.x, block.y) && mv.to() == (x, y)
+            })
+            .ok_or_else(illegal)?;
+
+        self.undone.clear();
+        if let Some((last, _)) = self.history.last().copied()
+            && last.to() == (block.x, block.y)
+            && last.piece == block.ty
+        {
+            let before = self
+                .history
+                .len()
+                .checked_sub(2)
+                .map_or(self.start, |i| self.history[i].1);
+            if before == next.1 {
+                self.history.pop();
+                return Ok(to);
+            }
+            //上一步之前的局面能一步到达时合并
+            let merged = before
+                .moves(&self.rules)
+                .into_iter()
+                .find(|(mv, _)| mv.piece == block.ty && mv.from == last.from && mv.to() == (x, y));
+            if let Some(merged) = merged {
+                *self.history.last_mut().unwrap() = merged;
+                return Ok(to);
+            }
+        }
+        self.history.push(next);
+        Ok(to)
+    }
+
+    /// 撤销一步, 返回撤销的移动
+    pub fn undo(&mut self) -> Option<Move> {
+        let e = self.history.pop()?;
+        self.undone.push(e);
+        Some(e.0)
+    }
+
+    /// 重做一步, 返回重做的移动
+    pub fn redo(&mut self) -> Option<Move> {
+        let e = self.undone.pop()?;
+        self.history.push(e);
+        Some(e.0)
+    }
+
+    /// 初始的 roster 执行已走的步之后的结果, 用来跟踪有名字的棋子
+    pub fn roster(&self, start: &Roster) -> Roster {
+        let mut ret = start.clone();
+        for e in self.moves() {
+            ret.apply(e);
+        }
+        ret
+    }
+}
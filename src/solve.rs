@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    rc::Rc,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::astar::{AStar, IdaStar};
+use crate::bidirectional::Bidirectional;
+use crate::board::NodeValue;
+use crate::game::{Game, Move, Rules};
+use crate::parallel::ParallelBfs;
+
+/// 搜索树上的节点, 通过 parent 回溯到初始局面
+#[derive(Debug)]
+pub struct Node {
+    pub val: NodeValue,
+    pub parent: Option<Rc<Node>>,
+}
+
+/// 搜索失败的原因, 其余错误均来自非法输入
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SolveError {
+    /// 所有可达局面已搜索完, 无解
+    NoSolution,
+    /// 搜索局面数量超过上限
+    LimitExceeded(usize),
+    /// 搜索中途被取消
+    Cancelled,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoSolution => write!(f, "can't find solve"),
+            SolveError::LimitExceeded(limit) => write!(f, "node size exceed {}", limit),
+            SolveError::Cancelled => write!(f, "search cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// 一个解: 初始局面和依次执行的移动
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Solution {
+    pub start: NodeValue,
+    pub moves: Vec<Move>,
+    /// 求解时使用的规则
+    pub rules: Rules,
+    /// 搜索过程中记录的局面数量
+    pub explored: usize,
+}
+
+impl Solution {
+    /// 步数
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// 依次经过的所有局面, 包括初始局面和终局
+    pub fn positions(&self) -> anyhow::Result<Vec<NodeValue>> {
+        let mut ret = vec![self.start];
+        for e in self.moves.iter() {
+            ret.push(ret.last().unwrap().apply(e, &self.rules)?);
+        }
+        Ok(ret)
+    }
+
+    fn from_node(node: &Node, rules: &Rules, explored: usize) -> anyhow::Result<Self> {
+        let mut states = vec![node.val];
+        let mut current_node = node;
+        while let Some(prev_node) = current_node.parent.as_deref() {
+            states.push(prev_node.val);
+            current_node = prev_node;
+        }
+        states.reverse();
+        Self::from_states(&states, rules, explored)
+    }
+
+    /// 由依次经过的局面得到解, 相邻局面之间必须恰好相差一步移动
+    pub(crate) fn from_states(
+        states: &[NodeValue],
+        rules: &Rules,
+        explored: usize,
+    ) -> anyhow::Result<Self> {
+        let mut moves = Vec::with_capacity(states.len() - 1);
+        for e in states.windows(2) {
+            moves.push(Game::new_unchecked(&e[0], rules).move_between(&e[1])?);
+        }
+        Ok(Self {
+            start: states[0],
+            moves,
+            rules: *rules,
+            explored,
+        })
+    }
+}
+
+/// 求解器的公共接口
+pub trait Solver {
+    /// 求解最短步骤, limit 为搜索局面数量上限
+    fn solve(&self, state: &NodeValue, limit: usize) -> anyhow::Result<Solution> {
+        self.solve_cancellable(state, limit, &AtomicBool::new(false))
+    }
+
+    /// 与 solve 相同, 另一个线程设置 cancel 后尽快返回 SolveError::Cancelled
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution>;
+}
+
+/// cancel 已设置时返回 SolveError::Cancelled, 各求解器每扩展一个局面检查一次
+pub(crate) fn check_cancel(cancel: &AtomicBool) -> Result<(), SolveError> {
+    if cancel.load(Ordering::Relaxed) {
+        Err(SolveError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// 从初始局面出发的广度优先搜索
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bfs {
+    pub rules: Rules,
+}
+
+impl Solver for Bfs {
+    fn solve_cancellable(
+        &self,
+        state: &NodeValue,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Solution> {
+        let (node, explored) = bfs(state, limit, &self.rules, cancel)?;
+        Solution::from_node(&node, &self.rules, explored)
+    }
+}
+
+/// 搜索算法
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Algorithm {
+    /// 从初始局面出发的广度优先搜索
+    #[default]
+    Bfs,
+    /// 同时从初始局面和所有终局出发的双向广度优先搜索
+    Bidirectional,
+    /// 以曹操到出口的距离为启发函数的 A* 搜索
+    AStar,
+    /// 迭代加深的 A* 搜索, 只保存当前路径和有限大小的置换表
+    IdaStar,
+    /// 使用所有 CPU 核心的逐层广度优先搜索, 结果与 Bfs 相同
+    Parallel,
+}
+
+impl Algorithm {
+    /// 按 rules 求解的求解器
+    pub fn solver(self, rules: Rules) -> Box<dyn Solver + Send + Sync> {
+        match self {
+            Algorithm::Bfs => Box::new(Bfs { rules }),
+            Algorithm::Bidirectional => Box::new(Bidirectional { rules }),
+            Algorithm::AStar => Box::new(AStar { rules }),
+            Algorithm::IdaStar => Box::new(IdaStar {
+                rules,
+                ..Default::default()
+            }),
+            Algorithm::Parallel => Box::new(ParallelBfs {
+                rules,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "bfs" => Ok(Algorithm::Bfs),
+            "bidirectional" | "bidir" => Ok(Algorithm::Bidirectional),
+            "astar" | "a*" => Ok(Algorithm::AStar),
+            "idastar" | "ida*" => Ok(Algorithm::IdaStar),
+            "parallel" => Ok(Algorithm::Parallel),
+            _ => anyhow::bail!("unknown algorithm {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    /// 与 from_str 相同的名字
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Algorithm::Bfs => "bfs",
+            Algorithm::Bidirectional => "bidirectional",
+            Algorithm::AStar => "astar",
+            Algorithm::IdaStar => "idastar",
+            Algorithm::Parallel => "parallel",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 求解最短步骤, limit 为搜索局面数量上限
+pub fn solve(state: &NodeValue, limit: usize) -> anyhow::Result<Solution> {
+    solve_with(state, limit, Algorithm::Bfs, Rules::default())
+}
+
+/// 使用指定算法按 rules 求解最短步骤
+pub fn solve_with(
+    state: &NodeValue,
+    limit: usize,
+    algorithm: Algorithm,
+    rules: Rules,
+) -> anyhow::Result<Solution> {
+    algorithm.solver(rules).solve(state, limit)
+}
+
+/// 将 hrd_solve 得到的解的每一步转换为 "(x,y) 方向" 形式的文字, 初始局面已是终局时为空
+pub fn step_messages(node: &Node) -> anyhow::Result<Vec<String>> {
+    let mut steps: Vec<String> = Vec::new();
+    let rules = Rules::default();
+    let mut current_node = node;
+    let mut current_game = Game::new_unchecked(&current_node.val, &rules);
+
+    while let Some(prev_node) = current_node.parent.as_deref() {
+        let prev_game = Game::new_unchecked(&prev_node.val, &rules);
+        let msg = prev_game.move_message(&current_game.state)?;
+        steps.push(msg);
+        current_node = prev_node;
+        current_game = prev_game;
+    }
+    steps.reverse();
+    Ok(steps)
+}
+
+/// 按直线滑动计步的广度优先搜索, 返回到达终局的节点
+pub fn hrd_solve(state: &NodeValue, limit: usize) -> anyhow::Result<Node> {
+    Ok(bfs(state, limit, &Rules::default(), &AtomicBool::new(false))?.0)
+}
+
+fn bfs(
+    state: &NodeValue,
+    limit: usize,
+    rules: &Rules,
+    cancel: &AtomicBool,
+) -> anyhow::Result<(Node, usize)> {
+    let game = Game::new(state, rules)?;
+    if rules.is_finish(state) {
+        return Ok((
+            Node {
+                val: game.state,
+                parent: None,
+            },
+            1,
+        ));
+    }
+
+    let mut set = HashSet::new();
+    let mut list = VecDeque::new();
+
+    set.insert(rules.key(&game.state));
+    list.push_back(Rc::new(Node {
+        val: game.state,
+        parent: None,
+    }));
+
+    let mut next_nodes = Vec::new();
+
+    loop {
+        let node = list.pop_front().ok_or(SolveError::NoSolution)?;
+        check_cancel(cancel)?;
+
+        let game = Game::new_unchecked(&node.val, rules);
+        game.next_nodes(&mut next_nodes);
+
+        for (_, e) in next_nodes.drain(..) {
+            if rules.is_finish(&e) {
+                let explored = set.len();
+                return Ok((
+                    Node {
+                        val: e,
+                        parent: Some(node),
+                    },
+                    explored,
+                ));
+            }
+            //镜像局面视为同一个, 但节点保留实际局面, 解仍按输入方向给出
+            if !set.insert(rules.key(&e)) {
+                continue;
+            }
+            list.push_back(Rc::new(Node {
+                val: e,
+                parent: Some(Rc::clone(&node)),
+            }));
+        }
+
+        if set.len() >= limit {
+            return Err(SolveError::LimitExceeded(limit).into());
+        }
+    }
+}
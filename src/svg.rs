@@ -0,0 +1,274 @@
+use std::fmt::Write;
+
+use crate::board::{BlockType, NodeValue};
+use crate::game::{Move, Rules};
+use crate::goal::Goal;
+use crate::notation::MoveFormatter;
+use crate::roster::Roster;
+use crate::solve::Solution;
+
+/// SVG 的尺寸和内容设置
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SvgOptions {
+    /// 每格的边长, 像素
+    pub cell: u32,
+    /// 是否在棋子上标出字母或者名字的字
+    pub labels: bool,
+    /// 分镜每行的局面数
+    pub columns: usize,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell: 40,
+            labels: true,
+            columns: 5,
+        }
+    }
+}
+
+//边框, 棋盘底色和箭头的颜色
+const FRAME_COLOR: &str = "#6b4f3a";
+const BOARD_COLOR: &str = "#f3ead8";
+const ARROW_COLOR: &str = "#222";
+
+/// 画出局面, 棋子按类型着色, 默认终局时在底边标出出口
+pub fn svg(state: &NodeValue, rules: &Rules, roster: &Roster, options: &SvgOptions) -> String {
+    let (width, height) = board_pixels(state, options);
+    let mut ret = header(width, height);
+    draw_board(&mut ret, state, rules, roster, options, (0.0, 0.0));
+    ret.push_str("</svg>\n");
+    ret
+}
+
+/// 分镜: 按 options.columns 排成网格画出解经过的每个局面, 用箭头标出从该局面走的下一步
+///
+/// 每个局面下方是序号和下一步的说明.
+pub fn storyboard(
+    solution: &Solution,
+    roster: &Roster,
+    formatter: &dyn MoveFormatter,
+    options: &SvgOptions,
+) -> anyhow::Result<String> {
+    let positions = solution.positions()?;
+    let cell = options.cell as f64;
+    let (board_width, board_height) = board_pixels(&solution.start, options);
+    let (panel_width, panel_height) = (board_width + cell / 2.0, board_height + cell);
+    let columns = options.columns.max(1);
+    let rows = positions.len().div_ceil(columns);
+    let mut ret = header(
+        panel_width * columns.min(positions.len()) as f64,
+        panel_height * rows as f64,
+    );
+    writeln!(
+        ret,
+        r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" markerWidth="4" markerHeight="4" orient="auto-start-reverse"><path d="M0,0 L10,5 L0,10 z" fill="{}"/></marker></defs>"#,
+        ARROW_COLOR
+    )?;
+
+    let mut names = roster.clone();
+    for (i, state) in positions.iter().enumerate() {
+        let origin = (
+            (i % columns) as f64 * panel_width,
+            (i / columns) as f64 * panel_height,
+        );
+        draw_board(&mut ret, state, &solution.rules, &names, options, origin);
+        let mut caption = i.to_string();
+        if let Some(mv) = solution.moves.get(i) {
+            draw_arrow(&mut ret, mv, &solution.rules, options, origin);
+            caption = format!("{}  {}", i, names.describe_with(mv, formatter));
+        }
+        writeln!(
+            ret,
+            r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" font-family="sans-serif">{}</text>"#,
+            origin.0 + board_width / 2.0,
+            origin.1 + board_height + cell * 0.5,
+            cell * 0.35,
+            escape(&caption)
+        )?;
+    }
+    ret.push_str("</svg>\n");
+    Ok(ret)
+}
+
+/// 棋子类型的填充色
+fn color(ty: BlockType) -> &'static str {
+    match ty {
+        BlockType::CaoCao => "#d9534f",
+        BlockType::Horizontal => "#428bca",
+        BlockType::Vertical => "#5cb85c",
+        BlockType::Pawn => "#f0ad4e",
+        BlockType::Custom(5) => "#9b59b6",
+        BlockType::Custom(6) => "#1abc9c",
+        BlockType::Custom(_) => "#a0785a",
+    }
+}
+
+/// 包括边框的棋盘大小
+fn board_pixels(state: &NodeValue, options: &SvgOptions) -> (f64, f64) {
+    let cell = options.cell as f64;
+    let (width, height) = state.size();
+    (
+        width as f64 * cell + cell / 2.0,
+        height as f64 * cell + cell / 2.0,
+    )
+}
+
+fn header(width: f64, height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        width, height
+    )
+}
+
+/// 在 origin 处画出带边框的棋盘和棋子
+fn draw_board(
+    out: &mut String,
+    state: &NodeValue,
+    rules: &Rules,
+    roster: &Roster,
+    options: &SvgOptions,
+    origin: (f64, f64),
+) {
+    let cell = options.cell as f64;
+    let pad = cell / 4.0;
+    let (width, height) = state.size();
+    let (board_width, board_height) = board_pixels(state, options);
+    let _ = writeln!(
+        out,
+        r#"<g transform="translate({},{})">"#,
+        origin.0, origin.1
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="{}" height="{}" rx="{}" fill="{}"/>"#,
+        board_width,
+        board_height,
+        pad / 2.0,
+        FRAME_COLOR
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        pad,
+        pad,
+        width as f64 * cell,
+        height as f64 * cell,
+        BOARD_COLOR
+    );
+    //出口处的边框留空
+    if rules.goal == Goal::Exit {
+        let columns = state.exit_columns();
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            pad + *columns.start() as f64 * cell,
+            pad + height as f64 * cell,
+            (columns.end() + 2 - columns.start()) as f64 * cell,
+            pad,
+            BOARD_COLOR
+        );
+    }
+
+    //棋子边缘向内收的距离, 相邻棋子之间留出缝
+    let inset = cell * 0.06;
+    let blocks = state.blocks(rules);
+    let mut owner = vec![None; width * height];
+    for (i, e) in blocks.iter().enumerate() {
+        let shape = rules.shapes.shape(e.ty);
+        for (x, y) in shape
+            .place((e.x, e.y), (width, height))
+            .into_iter()
+            .flatten()
+        {
+            owner[y * width + x] = Some(i);
+        }
+    }
+    for (i, e) in blocks.iter().enumerate() {
+        let shape = rules.shapes.shape(e.ty);
+        //(x, y) 是同一棋子的格子时不留缝
+        let gap = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) if x < width && y < height && owner[y * width + x] == Some(i) => 0.0,
+            _ => inset,
+        };
+        for (x, y) in shape
+            .place((e.x, e.y), (width, height))
+            .into_iter()
+            .flatten()
+        {
+            let left = gap(x.checked_sub(1), Some(y));
+            let right = gap(Some(x + 1), Some(y));
+            let top = gap(Some(x), y.checked_sub(1));
+            let bottom = gap(Some(x), Some(y + 1));
+            let _ = writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                pad + x as f64 * cell + left,
+                pad + y as f64 * cell + top,
+                cell - left - right,
+                cell - top - bottom,
+                color(e.ty)
+            );
+        }
+        if options.labels {
+            let label = roster
+                .get(e)
+                .map(|e| e.letter)
+                .or_else(|| rules.shapes.letter(e.ty))
+                .unwrap_or('?');
+            let (fx, fy) = shape.first();
+            let _ = writeln!(
+                out,
+                r##"<text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="central" font-family="sans-serif" fill="#fff">{}</text>"##,
+                pad + (e.x + fx) as f64 * cell + cell / 2.0,
+                pad + (e.y + fy) as f64 * cell + cell / 2.0,
+                cell * 0.45,
+                escape(&label.to_string())
+            );
+        }
+    }
+    out.push_str("</g>\n");
+}
+
+/// 从移动前到移动后画出棋子第一格中心经过的折线箭头
+fn draw_arrow(
+    out: &mut String,
+    mv: &Move,
+    rules: &Rules,
+    options: &SvgOptions,
+    origin: (f64, f64),
+) {
+    let cell = options.cell as f64;
+    let pad = cell / 4.0;
+    let (fx, fy) = rules.shapes.shape(mv.piece).first();
+    let (mut x, mut y) = ((mv.from.0 + fx) as i32, (mv.from.1 + fy) as i32);
+    let center = |x: i32, y: i32| {
+        format!(
+            "{},{}",
+            origin.0 + pad + x as f64 * cell + cell / 2.0,
+            origin.1 + pad + y as f64 * cell + cell / 2.0
+        )
+    };
+    let mut points = vec![center(x, y)];
+    for e in mv.path.iter() {
+        let (dx, dy) = e.delta();
+        x += dx;
+        y += dy;
+        points.push(center(x, y));
+    }
+    let _ = writeln!(
+        out,
+        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round" marker-end="url(#arrow)"/>"#,
+        points.join(" "),
+        ARROW_COLOR,
+        cell * 0.08
+    );
+}
+
+/// XML 文字转义
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
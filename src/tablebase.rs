@@ -0,0 +1,355 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    path::Path,
+};
+
+use crate::board::{HEIGHT, NodeValue, Shape, Shapes, WIDTH};
+use crate::game::{Game, Metric, Move, Rules};
+use crate::goal::{Goal, Pattern};
+use crate::solve::SolveError;
+
+const MAGIC: &[u8; 4] = b"HRDT";
+const VERSION: u32 = 1;
+/// 无法到达终局的局面记录的距离
+const UNSOLVABLE: u16 = u16::MAX;
+
+/// 一个连通分量内所有局面到最近终局的步数
+///
+/// 以镜像合并后的局面为键, 互为镜像的局面距离相同.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Tablebase {
+    rules: Rules,
+    distances: HashMap<NodeValue, u16>,
+}
+
+impl Tablebase {
+    /// 枚举 start 可达的全部局面, 再从其中的终局反向广度优先搜索得到每个局面按 rules 计步的距离
+    pub fn build(start: &NodeValue, rules: Rules, limit: usize) -> anyhow::Result<Self> {
+        Game::new(start, &rules)?;
+
+        //邻接表, 下标为局面在 states 中的位置
+        let mut index = HashMap::new();
+        let mut states = vec![rules.key(start)];
+        let mut edges: Vec<Vec<u32>> = Vec::new();
+        index.insert(states[0], 0u32);
+
+        let mut next_nodes = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            Game::new_unchecked(&states[i], &rules).next_nodes(&mut next_nodes);
+            let mut neighbors = Vec::with_capacity(next_nodes.len());
+            for (_, e) in next_nodes.drain(..) {
+                let key = rules.key(&e);
+                let next = *index.entry(key).or_insert_with(|| {
+                    states.push(key);
+                    (states.len() - 1) as u32
+                });
+                neighbors.push(next);
+            }
+            edges.push(neighbors);
+            i += 1;
+
+            if states.len() >= limit {
+                return Err(SolveError::LimitExceeded(limit).into());
+            }
+        }
+
+        //移动都可逆, 从所有终局出发的广度优先搜索即得到每个局面到最近终局的步数
+        let mut distance = vec![UNSOLVABLE; states.len()];
+        let mut list = VecDeque::new();
+        for (i, e) in states.iter().enumerate() {
+            if rules.is_finish(e) {
+                distance[i] = 0;
+                list.push_back(i);
+            }
+        }
+        while let Some(i) = list.pop_front() {
+            anyhow::ensure!(
+                distance[i] < UNSOLVABLE - 1,
+                "distance exceed {}",
+                UNSOLVABLE
+            );
+            for &next in edges[i].iter() {
+                let next = next as usize;
+                if distance[next] == UNSOLVABLE {
+                    distance[next] = distance[i] + 1;
+                    list.push_back(next);
+                }
+            }
+        }
+
+        Ok(Self {
+            rules,
+            distances: states.into_iter().zip(distance).collect(),
+        })
+    }
+
+    /// 计算距离时使用的计步方式和棋子形状
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// 记录的局面数量
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// 局面是否在表中, 包括无法到达终局的局面
+    pub fn contains(&self, state: &NodeValue) -> bool {
+        self.distances.contains_key(&self.rules.key(state))
+    }
+
+    /// 到最近终局的步数, 局面不在表中或无法到达终局时返回 None
+    pub fn distance(&self, state: &NodeValue) -> Option<usize> {
+        match self.distances.get(&self.rules.key(state)) {
+            Some(&UNSOLVABLE) | None => None,
+            Some(&e) => Some(e as usize),
+        }
+    }
+
+    /// 所有让距离减少 1 的移动, 已是终局或无解时为空
+    pub fn best_moves(&self, state: &NodeValue) -> Vec<Move> {
+        let Some(distance) = self.distance(state).filter(|e| *e > 0) else {
+            return Vec::new();
+        };
+
+        state
+            .moves(&self.rules)
+            .into_iter()
+            .filter(|e| self.distance(&e.1) == Some(distance - 1))
+            .map(|e| e.0)
+            .collect()
+    }
+
+    /// 文件格式 (小端):
+    /// "HRDT", 版本 u32, 宽 u8, 高 u8, 计步方式 u8, 自定义形状数量 u8, 局面数量 u64,
+    /// 每种自定义形状的 (字母 u32, 掩码长度 u8, 掩码), 终局条件 (见 write_goal),
+    /// 按编码排序的 (局面编码 u128, 距离 u16), 最后是之前所有字节的 CRC32
+    pub fn write_to<W: Write>(&self, w: W) -> anyhow::Result<()> {
+        let (width, height) = self
+            .distances
+            .keys()
+            .next()
+            .map_or((WIDTH, HEIGHT), |e| e.size());
+        let mut w = Crc32Writer::new(w);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        let shapes: Vec<_> = self.rules.shapes.iter().collect();
+        w.write_all(&[
+            width as u8,
+            height as u8,
+            metric_code(self.rules.metric),
+            shapes.len() as u8,
+        ])?;
+        w.write_all(&(self.distances.len() as u64).to_le_bytes())?;
+        for (_, letter, shape) in shapes {
+            let mask = shape.to_string();
+            w.write_all(&(letter as u32).to_le_bytes())?;
+            w.write_all(&[mask.len() as u8])?;
+            w.write_all(mask.as_bytes())?;
+        }
+        self.write_goal(&mut w)?;
+
+        let mut entries: Vec<_> = self.distances.iter().collect();
+        entries.sort();
+        for (state, distance) in entries {
+            w.write_all(&state.bits().to_le_bytes())?;
+            w.write_all(&distance.to_le_bytes())?;
+        }
+
+        let crc = w.crc;
+        w.inner.write_all(&crc.to_le_bytes())?;
+        w.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut r: R) -> anyhow::Result<Self> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        anyhow::ensure!(data.len() >= 24, "tablebase too short");
+
+        let (body, crc) = data.split_at(data.len() - 4);
+        let crc = u32::from_le_bytes(crc.try_into()?);
+        anyhow::ensure!(crc32(0, body) == crc, "tablebase checksum mismatch");
+
+        anyhow::ensure!(&body[0..4] == MAGIC, "not a tablebase file");
+        let version = u32::from_le_bytes(body[4..8].try_into()?);
+        anyhow::ensure!(
+            version == VERSION,
+            "unsupported tablebase version {}",
+            version
+        );
+        let (width, height) = (body[8] as usize, body[9] as usize);
+        NodeValue::new(width, height)?;
+        let metric = metric_from_code(body[10])?;
+        let count = u64::from_le_bytes(body[12..20].try_into()?) as usize;
+        let mut entries = &body[20..];
+        let mut shapes = Shapes::default();
+        for _ in 0..body[11] {
+            let letter = read_letter(&mut entries)?;
+            let len = take(&mut entries, 1)?[0] as usize;
+            let mask = std::str::from_utf8(take(&mut entries, len)?)?;
+            shapes.define(letter, Shape::parse(mask)?)?;
+        }
+        let goal = read_goal(&mut entries, &shapes, (width, height))?;
+        let entry_len = 16 + 2;
+        anyhow::ensure!(
+            count.checked_mul(entry_len) == Some(entries.len()),
+            "tablebase size mismatch, {} entries",
+            count
+        );
+
+        let mut distances = HashMap::with_capacity(count);
+        for e in entries.chunks_exact(entry_len) {
+            let state = NodeValue::from_bits(u128::from_le_bytes(e[..16].try_into()?))?;
+            anyhow::ensure!(
+                state.size() == (width, height),
+                "tablebase entry size {}x{} mismatch",
+                state.width(),
+                state.height()
+            );
+            distances.insert(state, u16::from_le_bytes(e[16..].try_into()?));
+        }
+        Ok(Self {
+            rules: Rules {
+                metric,
+                shapes,
+                goal,
+            },
+            distances,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_to(std::io::BufWriter::new(file))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::read_from(std::io::BufReader::new(file))
+    }
+}
+
+impl Tablebase {
+    /// 终局类型 u8: 0 曹操到达出口, 1 目标棋子, 之后是 (字母 u32, x u8, y u8),
+    /// 2 图案, 之后是 (图案编码 u128, 固定格子的位 u128)
+    fn write_goal<W: Write>(&self, w: &mut Crc32Writer<W>) -> anyhow::Result<()> {
+        match self.rules.goal {
+            Goal::Exit => w.write_all(&[0])?,
+            Goal::Piece { ty, x, y } => {
+                let letter = self.rules.shapes.letter(ty).unwrap_or_default();
+                w.write_all(&[1])?;
+                w.write_all(&(letter as u32).to_le_bytes())?;
+                w.write_all(&[x as u8, y as u8])?;
+            }
+            Goal::Pattern(e) => {
+                w.write_all(&[2])?;
+                w.write_all(&e.cells.bits().to_le_bytes())?;
+                w.write_all(&e.mask.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_goal(data: &mut &[u8], shapes: &Shapes, size: (usize, usize)) -> anyhow::Result<Goal> {
+    match take(data, 1)?[0] {
+        0 => Ok(Goal::Exit),
+        1 => {
+            let letter = read_letter(data)?;
+            let ty = shapes
+                .ty(letter)
+                .ok_or_else(|| anyhow::anyhow!("unknown tablebase goal piece {}", letter))?;
+            let pos = take(data, 2)?;
+            Goal::piece(ty, (pos[0] as usize, pos[1] as usize), shapes, size)
+        }
+        2 => {
+            let cells = NodeValue::from_bits(u128::from_le_bytes(take(data, 16)?.try_into()?))?;
+            anyhow::ensure!(cells.size() == size, "tablebase goal size mismatch");
+            let mask = u128::from_le_bytes(take(data, 16)?.try_into()?);
+            Ok(Goal::Pattern(Pattern { cells, mask }))
+        }
+        e => anyhow::bail!("unknown tablebase goal {}", e),
+    }
+}
+
+fn read_letter(data: &mut &[u8]) -> anyhow::Result<char> {
+    let letter = u32::from_le_bytes(take(data, 4)?.try_into()?);
+    char::from_u32(letter).ok_or_else(|| anyhow::anyhow!("bad tablebase letter {}", letter))
+}
+
+/// 取出 data 开头的 len 个字节
+fn take<'a>(data: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+    anyhow::ensure!(data.len() >= len, "tablebase too short");
+    let (ret, rest) = data.split_at(len);
+    *data = rest;
+    Ok(ret)
+}
+
+fn metric_code(metric: Metric) -> u8 {
+    match metric {
+        Metric::Slide => 0,
+        Metric::Cell => 1,
+        Metric::Piece => 2,
+    }
+}
+
+fn metric_from_code(code: u8) -> anyhow::Result<Metric> {
+    match code {
+        0 => Ok(Metric::Slide),
+        1 => Ok(Metric::Cell),
+        2 => Ok(Metric::Piece),
+        _ => anyhow::bail!("unknown tablebase metric {}", code),
+    }
+}
+
+struct Crc32Writer<W> {
+    inner: W,
+    crc: u32,
+}
+
+impl<W: Write> Crc32Writer<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, crc: 0 }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.crc = crc32(self.crc, buf);
+        self.inner.write_all(buf)
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE), crc 为之前数据的结果, 可以分段计算
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &e in data {
+        c = CRC32_TABLE[((c ^ e as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
@@ -0,0 +1,173 @@
+//! 终端中的华容道游戏
+
+use std::io::{IsTerminal, Write};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use hrd::{Algorithm, Block, Direction, HintSource, MoveFormatter, Roster, Session};
+
+use crate::play::draw_board;
+
+/// 进入全屏和 raw 模式, 退出时恢复终端
+struct Screen;
+
+impl Screen {
+    fn enter() -> anyhow::Result<Self> {
+        terminal::enable_raw_mode()?;
+        print!("\x1b[?1049h\x1b[?25l");
+        std::io::stdout().flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// 游戏的设置
+pub struct Options<'a> {
+    /// 提示和最少步数使用的算法
+    pub algorithm: Algorithm,
+    pub limit: usize,
+    pub formatter: &'a dyn MoveFormatter,
+}
+
+/// 在终端中玩一局
+///
+/// Tab 选择棋子, 方向键移动选中的棋子, u 撤销, r 重做, h 提示, q 退出.
+/// 到达终局后显示所用步数和最少步数.
+pub fn play(session: &mut Session, roster: &Roster, options: &Options) -> anyhow::Result<()> {
+    anyhow::ensure!(std::io::stdout().is_terminal(), "play needs a terminal");
+    let _screen = Screen::enter()?;
+    let mut selected = 0;
+    let mut message = String::new();
+    //从初始局面求出的最少步数, 第一次到达终局时计算
+    let mut best = None;
+    loop {
+        let blocks = session.blocks();
+        selected = selected.min(blocks.len() - 1);
+        let names = session.roster(roster);
+
+        let mut lines = vec![format!("步数 {}", session.len())];
+        lines.extend(draw_board(
+            session.state(),
+            &session.rules,
+            &names,
+            Some(blocks[selected]),
+        ));
+        if session.is_finish() {
+            let best = best.get_or_insert_with(|| {
+                options
+                    .algorithm
+                    .solver(session.rules)
+                    .solve(session.start(), options.limit)
+                    .map(|e| e.len())
+                    .map_err(|e| e.to_string())
+            });
+            lines.push(finish_message(session.len(), best));
+            lines.push("u 撤销  q 退出".to_string());
+        } else {
+            lines.push(message.clone());
+            lines.push("Tab 选择  方向键 移动  u 撤销  r 重做  h 提示  q 退出".to_string());
+        }
+        let mut frame = String::from("\x1b[H\x1b[J");
+        for e in lines {
+            frame.push_str(&e);
+            frame.push_str("\r\n");
+        }
+        std::io::stdout().write_all(frame.as_bytes())?;
+        std::io::stdout().flush()?;
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        message.clear();
+        let direction = match code {
+            KeyCode::Up | KeyCode::Char('w') => Some(Direction::Up),
+            KeyCode::Down | KeyCode::Char('s') => Some(Direction::Down),
+            KeyCode::Left | KeyCode::Char('a') => Some(Direction::Left),
+            KeyCode::Right | KeyCode::Char('d') => Some(Direction::Right),
+            _ => None,
+        };
+        if let Some(direction) = direction {
+            if session.is_finish() {
+                continue;
+            }
+            match session.step(blocks[selected], direction) {
+                Ok(block) => selected = index_of(session, block, selected),
+                Err(_) => message = "不能这样移动".to_string(),
+            }
+            continue;
+        }
+        match code {
+            KeyCode::Tab => selected = (selected + 1) % blocks.len(),
+            KeyCode::BackTab => selected = (selected + blocks.len() - 1) % blocks.len(),
+            KeyCode::Char('u') => match session.undo() {
+                Some(mv) => {
+                    let (x, y) = mv.from;
+                    selected = index_of(session, Block { ty: mv.piece, x, y }, selected);
+                }
+                None => message = "没有可以撤销的步".to_string(),
+            },
+            KeyCode::Char('r') => match session.redo() {
+                Some(mv) => {
+                    let (x, y) = mv.to();
+                    selected = index_of(session, Block { ty: mv.piece, x, y }, selected);
+                }
+                None => message = "没有可以重做的步".to_string(),
+            },
+            KeyCode::Char('h') if !session.is_finish() => {
+                let source = HintSource::Search {
+                    algorithm: options.algorithm,
+                    rules: session.rules,
+                    limit: options.limit,
+                };
+                message = match hrd::hint(session.state(), source) {
+                    Ok(hint) => match hint.moves.first() {
+                        Some(mv) => {
+                            let (x, y) = mv.from;
+                            selected = index_of(session, Block { ty: mv.piece, x, y }, selected);
+                            let text = names.clone().describe_with(mv, options.formatter);
+                            format!("提示: 还需 {} 步, 下一步 {}", hint.distance, text)
+                        }
+                        None => "已到达终局".to_string(),
+                    },
+                    Err(e) => format!("提示失败: {}", e),
+                };
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// 选中 block 的序号, 找不到时保持 selected
+fn index_of(session: &Session, block: Block, selected: usize) -> usize {
+    session
+        .blocks()
+        .iter()
+        .position(|e| *e == block)
+        .unwrap_or(selected)
+}
+
+/// 到达终局时的说明, 与最少步数比较
+fn finish_message(steps: usize, best: &Result<usize, String>) -> String {
+    match best {
+        Ok(best) if *best == steps => format!("完成! 用了 {} 步, 正是最少步数", steps),
+        Ok(best) => format!("完成! 用了 {} 步, 最少 {} 步", steps, best),
+        Err(e) => format!("完成! 用了 {} 步, 最少步数未知: {}", steps, e),
+    }
+}
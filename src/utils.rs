@@ -1,10 +1,32 @@
+use log::LevelFilter;
+use log4rs::append::console::Target;
+
+//库的测试和命令行共用这个文件, 各自只用到其中一个
+
+/// 初始化日志, 重复调用时只有第一次生效
+#[allow(dead_code)]
 pub fn init_log() {
-    use log::LevelFilter;
+    init_log_with(LevelFilter::Trace, Target::Stdout);
+}
+
+/// 以指定级别输出日志到 stderr, 命令行使用, 避免与 stdout 上的结果混在一起
+#[allow(dead_code)]
+pub fn init_stderr_log(level: LevelFilter) {
+    init_log_with(level, Target::Stderr);
+}
+
+fn init_log_with(level: LevelFilter, target: Target) {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| init_log_once(level, target));
+}
+
+fn init_log_once(level: LevelFilter, target: Target) {
     use log4rs::append::console::ConsoleAppender;
     use log4rs::config::{Appender, Config, Root};
     use log4rs::encode::pattern::PatternEncoder;
 
     let stdout = ConsoleAppender::builder()
+        .target(target)
         .encoder(Box::new(PatternEncoder::new(
             "{d(%y-%m-%d %H:%M:%S%.3f)} {h({level}):5} {I} [{T}] {t} -- {m}{n}",
         )))
@@ -12,7 +34,7 @@ pub fn init_log() {
 
     let config = Config::builder()
         .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .build(Root::builder().appender("stdout").build(LevelFilter::Trace))
+        .build(Root::builder().appender("stdout").build(level))
         .unwrap();
 
     log4rs::init_config(config).unwrap();
@@ -33,16 +55,3 @@ pub fn init_log() {
         }
     }));
 }
-
-#[macro_export]
-macro_rules! log_guard {
-    ($v:expr) => {{
-        let v = $v;
-        if v.is_err() {
-            log::error!("{}", v.err().unwrap());
-            return;
-        } else {
-            v.unwrap()
-        }
-    }};
-}
@@ -0,0 +1,177 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::board::{Block, BlockType, MAX_CELLS, NodeValue};
+use crate::game::Rules;
+use crate::goal::Goal;
+
+/// 输入中的一个问题
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Problem {
+    /// 问题所在的格子 (列, 行), 与具体格子无关时为 None
+    pub pos: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Problem {
+    pub(crate) fn at(x: usize, y: usize, message: String) -> Self {
+        Self {
+            pos: Some((x, y)),
+            message,
+        }
+    }
+
+    pub(crate) fn new(message: String) -> Self {
+        Self { pos: None, message }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// 检查输入时发现的所有问题
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.problems.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// 检查每个棋子是否完整, 终局为从出口走出时还要恰好有一个曹操, 一次给出所有问题
+pub fn validate(state: &NodeValue, rules: &Rules) -> Result<(), ValidationError> {
+    let mut problems = Vec::new();
+    check_blocks(state, rules, &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+/// 按行优先解析出所有完整的棋子和空格, 问题记入 problems 后继续检查其余的格子
+pub(crate) fn check_blocks(
+    state: &NodeValue,
+    rules: &Rules,
+    problems: &mut Vec<Problem>,
+) -> (Vec<Block>, Vec<(usize, usize)>) {
+    let (width, height) = state.size();
+    let mut blocks = Vec::with_capacity(10);
+    let mut empty_cell = Vec::with_capacity(2);
+    let mut visited = [false; MAX_CELLS];
+    let mut caocao = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if visited[y * width + x] {
+                continue;
+            }
+            let Some(ty) = state.get(x, y) else {
+                empty_cell.push((x, y));
+                continue;
+            };
+            if !rules.shapes.contains(ty) {
+                visited[y * width + x] = true;
+                problems.push(Problem::at(
+                    x,
+                    y,
+                    format!("undefined piece type at ({},{})", x, y),
+                ));
+                continue;
+            }
+            if ty == BlockType::CaoCao {
+                caocao.push((x, y));
+            }
+
+            //从形状的第一格推出外接矩形左上角, 每格都必须是同一类型且未被占据
+            let shape = rules.shapes.shape(ty);
+            let (fx, fy) = shape.first();
+            let (ox, oy) = (x as isize - fx as isize, y as isize - fy as isize);
+            let mut outside = false;
+            let mut missing = Vec::new();
+            for (dx, dy) in shape.cells() {
+                let (cx, cy) = (ox + dx as isize, oy + dy as isize);
+                if cx < 0 || cy < 0 || cx >= width as isize || cy >= height as isize {
+                    outside = true;
+                    continue;
+                }
+                let (cx, cy) = (cx as usize, cy as usize);
+                if state.get(cx, cy) == Some(ty) && !visited[cy * width + cx] {
+                    visited[cy * width + cx] = true;
+                } else {
+                    missing.push((cx, cy));
+                }
+            }
+            if !outside && missing.is_empty() {
+                blocks.push(Block {
+                    ty,
+                    x: ox as usize,
+                    y: oy as usize,
+                });
+                continue;
+            }
+
+            let name = piece_name(ty, rules);
+            let message = match ty {
+                BlockType::Vertical => format!("{} at ({},{}) has no lower half", name, x, y),
+                BlockType::Horizontal => format!("{} at ({},{}) has no right half", name, x, y),
+                _ if outside => format!("{} at ({},{}) extends outside the board", name, x, y),
+                _ => {
+                    let cells: Vec<_> = missing
+                        .iter()
+                        .map(|(x, y)| format!("({},{})", x, y))
+                        .collect();
+                    format!(
+                        "{} at ({},{}) has no cells at {}",
+                        name,
+                        x,
+                        y,
+                        cells.join(", ")
+                    )
+                }
+            };
+            problems.push(Problem::at(x, y, message));
+        }
+    }
+
+    //只有从出口走出时需要恰好一个曹操
+    if rules.goal == Goal::Exit {
+        if caocao.is_empty() {
+            problems.push(Problem::new(
+                "there must be exactly one CaoCao, found none".to_string(),
+            ));
+        }
+        for &(x, y) in caocao.iter().skip(1) {
+            problems.push(Problem::at(
+                x,
+                y,
+                format!("extra CaoCao at ({},{}), there must be exactly one", x, y),
+            ));
+        }
+    }
+    (blocks, empty_cell)
+}
+
+fn piece_name(ty: BlockType, rules: &Rules) -> String {
+    match ty {
+        BlockType::CaoCao => "CaoCao".to_string(),
+        BlockType::Horizontal => "horizontal piece".to_string(),
+        BlockType::Vertical => "vertical piece".to_string(),
+        BlockType::Pawn => "pawn".to_string(),
+        BlockType::Custom(_) => format!("piece {}", rules.shapes.letter(ty).unwrap_or('?')),
+    }
+}
@@ -0,0 +1,31 @@
+//! 只通过公开接口使用库, 在 stable 上编译
+
+#[test]
+fn test_public_api() {
+    let board: hrd::Board = hrd::parse_state(
+        r#"
+        vvxv
+        vvxv
+        vvcc
+        vvcc
+        pppp
+        "#,
+    )
+    .unwrap();
+    let solution = hrd::solve(&board, 1024).unwrap();
+    assert_eq!(solution.len(), 8);
+    assert_eq!(solution.positions().unwrap().len(), 9);
+    let last = *solution.positions().unwrap().last().unwrap();
+    assert!(solution.rules.is_finish(&last));
+
+    //旧接口给出同样的步数
+    let node = hrd::hrd_solve(&board, 1024).unwrap();
+    assert_eq!(hrd::step_messages(&node).unwrap().len(), 8);
+
+    //超出上限时给出 SolveError
+    let e = hrd::solve(&board, 2).unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<hrd::SolveError>(),
+        Some(&hrd::SolveError::LimitExceeded(2))
+    );
+}
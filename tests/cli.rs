@@ -0,0 +1,153 @@
+//! 命令行的退出码, 参数和输出格式
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+fn blocks() -> &'static str {
+    r#"
+    vvxv
+    vvxv
+    vvcc
+    vvcc
+    pppp
+    "#
+}
+
+/// 运行 hrd, 返回退出码, stdout 和 stderr
+fn hrd(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hrd"))
+        .args(args)
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    //参数错误时不读 stdin, 写入会失败
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_exit_code() {
+    let (code, stdout, _) = hrd(&["solve", "-"], blocks());
+    assert_eq!(code, 0);
+    assert!(stdout.starts_with("8 steps\n"));
+    assert_eq!(stdout.lines().count(), 9);
+
+    //没有空格, 无法移动
+    let (code, _, stderr) = hrd(&["solve", "-"], "vccv\nvccv\nvhhv\nvppv\npppp");
+    assert_eq!(code, 1);
+    assert!(stderr.contains("can't find solve"));
+
+    let (code, _, stderr) = hrd(&["solve", "-l", "2", "-"], blocks());
+    assert_eq!(code, 2);
+    assert!(stderr.contains("node size exceed 2"));
+
+    let (code, _, stderr) = hrd(&["solve", "-"], "vvxv\nvvxv\nvv?c\nvvcc\npppp");
+    assert_eq!(code, 3);
+    assert!(stderr.contains("unknown token ? at (2,2)"));
+    let (code, _, stderr) = hrd(&["solve", "no-such-file.txt"], "");
+    assert_eq!(code, 3);
+    assert!(stderr.contains("no-such-file.txt"));
+}
+
+#[test]
+fn test_args() {
+    for args in [
+        &["solve", "-l", "abc", "-"][..],
+        &["solve", "--bogus", "-"],
+        &["solve"],
+        &["solve", "a", "b"],
+        &["solve", "-f", "xml", "-"],
+        &["nope"],
+    ] {
+        let (code, stdout, stderr) = hrd(args, blocks());
+        assert_eq!(code, 4, "{:?}", args);
+        assert!(stdout.is_empty() && stderr.contains("用法:"), "{:?}", args);
+    }
+    let (code, stdout, _) = hrd(&["help"], "");
+    assert_eq!(code, 0);
+    assert!(stdout.contains("hrd solve"));
+}
+
+#[test]
+fn test_format() {
+    let (code, stdout, _) = hrd(&["solve", "-f", "json", "-"], blocks());
+    assert_eq!(code, 0);
+    let report: hrd::Report = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report.solution.len(), 8);
+    assert_eq!(report.solution.start, hrd::parse_state(blocks()).unwrap());
+
+    let (code, stdout, _) = hrd(&["solve", "-f", "csv", "-"], blocks());
+    assert_eq!(code, 0);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert!(lines[0].starts_with("step,piece,"));
+    assert!(lines[1].starts_with("0,,,,,,,,"));
+
+    //log 格式只输出到 stderr
+    let (code, stdout, stderr) = hrd(&["solve", "-f", "log", "-"], blocks());
+    assert_eq!(code, 0);
+    assert!(stdout.is_empty() && stderr.contains("8 steps"));
+}
+
+#[test]
+fn test_position_code() {
+    let code = hrd::parse_state(blocks()).unwrap().encode();
+    let (status, stdout, _) = hrd(&["code", "-"], blocks());
+    assert_eq!(status, 0);
+    assert_eq!(stdout.trim(), code);
+
+    //没有这个名字的文件时按局面码读取
+    let (status, stdout, _) = hrd(&["solve", &code], "");
+    assert_eq!(status, 0);
+    assert!(stdout.starts_with("8 steps\n"));
+
+    //同名文件优先
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(dir.join(&code), "vvxv\nvvxv\nvvcc\nvvcc\npppx").unwrap();
+    let (status, stdout, _) = hrd(&["code", &code], "");
+    std::fs::remove_file(dir.join(&code)).unwrap();
+    assert_eq!(status, 0);
+    assert_ne!(stdout.trim(), code);
+
+    //局面码有问题时与文件不存在一样是输入错误
+    let (status, _, stderr) = hrd(&["solve", "45LZLZTajcE"], "");
+    assert_eq!(status, 3);
+    assert!(!stderr.is_empty());
+}
+
+#[test]
+fn test_play_solution() {
+    let (code, steps, _) = hrd(&["solve", "-"], blocks());
+    assert_eq!(code, 0);
+    let steps: Vec<_> = steps.lines().skip(1).collect();
+
+    //不在终端中时依次输出每一帧, 不移动光标
+    let (code, stdout, _) = hrd(&["play-solution", "-d", "0", "-"], blocks());
+    assert_eq!(code, 0);
+    assert!(!stdout.contains("\x1b[?25") && !stdout.contains("A\r"));
+    let lines: Vec<_> = stdout.lines().collect();
+    //每帧一行说明, 5 行棋盘各 3 行减去最后的缝, 再加上下边框
+    let height = 1 + 5 * 3 - 1 + 2;
+    assert_eq!(lines.len(), (steps.len() + 1) * height);
+    for (i, frame) in lines.chunks(height).enumerate() {
+        let text = if i == 0 { "初始局面" } else { steps[i - 1] };
+        assert_eq!(frame[0], format!("{}/{}  {}", i, steps.len(), text));
+        assert!(frame[1].starts_with('┌') && frame[height - 1].starts_with('└'));
+        //刚移动的棋子用亮色
+        let bright = frame.iter().any(|e| e.contains("\x1b[10"));
+        assert_eq!(bright, i > 0, "frame {}", i);
+    }
+    //终局时曹操在底部正中
+    let last = &lines[lines.len() - 3];
+    assert!(last.contains("\x1b[41;30m") || last.contains("\x1b[101;30m"));
+}
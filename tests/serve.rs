@@ -0,0 +1,177 @@
+//! hrd serve 的各个接口
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+/// 在随机端口启动的服务, 结束时关闭
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_hrd"))
+            .args(["serve", "--port", "0"])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        //从日志中读出实际监听的端口
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+        let mut line = String::new();
+        while !line.contains("listening on") {
+            line.clear();
+            assert!(stderr.read_line(&mut line).unwrap() > 0, "server exited");
+        }
+        let port = line.trim().rsplit(':').next().unwrap().parse().unwrap();
+        //继续读日志, 避免管道写满
+        std::thread::spawn(move || std::io::copy(&mut stderr, &mut std::io::sink()));
+        Self { child, port }
+    }
+
+    /// 发出请求, 返回状态码, Content-Type 和内容
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String, Vec<u8>) {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response.windows(4).position(|e| e == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(response[..split].to_vec()).unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        let content_type = head
+            .lines()
+            .find_map(|e| e.strip_prefix("Content-Type: "))
+            .unwrap_or_default()
+            .to_string();
+        (status, content_type, response[split + 4..].to_vec())
+    }
+
+    fn post(&self, path: &str, body: serde_json::Value) -> (u16, serde_json::Value) {
+        let (status, content_type, body) = self.request("POST", path, &body.to_string());
+        assert_eq!(content_type, "application/json");
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn blocks() -> &'static str {
+    "vvxv\nvvxv\nvvcc\nvvcc\npppp"
+}
+
+#[test]
+fn test_endpoints() {
+    use serde_json::json;
+
+    let server = Server::start(&["-j", "2"]);
+    let (status, body) = server.post("/solve", json!({"board": blocks()}));
+    assert_eq!(status, 200);
+    assert_eq!(body["steps"], 8);
+    assert_eq!(body["moves"].as_array().unwrap().len(), 8);
+
+    let (status, body) = server.post("/hint", json!({"board": blocks()}));
+    assert_eq!(status, 200);
+    assert_eq!(body["distance"], 8);
+    assert_eq!(body["moves"].as_array().unwrap().len(), 1);
+
+    let (status, body) = server.post("/validate", json!({"board": "45LZLZTajcEg"}));
+    assert_eq!((status, &body["valid"]), (200, &json!(true)));
+    let (status, body) = server.post("/validate", json!({"board": "cc\ncv"}));
+    assert_eq!((status, &body["valid"]), (200, &json!(false)));
+    assert!(!body["problems"].as_array().unwrap().is_empty());
+
+    let (status, content_type, image) = server.request(
+        "POST",
+        "/render",
+        &json!({"board": blocks(), "format": "gif", "tweens": 0}).to_string(),
+    );
+    assert_eq!((status, content_type.as_str()), (200, "image/gif"));
+    assert!(image.starts_with(b"GIF89a"));
+    let (status, content_type, image) =
+        server.request("POST", "/render", &json!({"board": blocks()}).to_string());
+    assert_eq!((status, content_type.as_str()), (200, "image/svg+xml"));
+    assert!(image.starts_with(b"<svg"));
+}
+
+#[test]
+fn test_errors() {
+    use serde_json::json;
+
+    let server = Server::start(&["-j", "2", "-l", "100000"]);
+    //超过上限的图片参数不会分配内存, 服务继续运行
+    for params in [
+        json!({"board": "45LZLZTajcEg", "format": "gif", "cell": 65535}),
+        json!({"board": "45LZLZTajcEg", "format": "gif", "tweens": 1000}),
+        json!({"board": "45LZLZTajcEg", "format": "storyboard", "columns": 100000}),
+    ] {
+        let (status, body) = server.post("/render", params);
+        assert_eq!((status, &body["error"]), (400, &json!("bad_request")));
+        assert!(body["message"].as_str().unwrap().contains("exceeds"));
+    }
+    let (status, _) = server.post("/validate", json!({"board": blocks()}));
+    assert_eq!(status, 200);
+
+    let (status, body) = server.post("/solve", json!({"board": blocks(), "limit": 2}));
+    assert_eq!((status, &body["error"]), (422, &json!("limit_exceeded")));
+    let (status, body) = server.post("/solve", json!({"board": "vccv\nvccv\nvhhv\nvppv\npppp"}));
+    assert_eq!((status, &body["error"]), (422, &json!("no_solution")));
+    let (status, body) = server.post("/solve", json!({"board": "cc\ncv"}));
+    assert_eq!((status, &body["error"]), (400, &json!("invalid_board")));
+    assert!(!body["problems"].as_array().unwrap().is_empty());
+
+    let (status, body) = server.post("/solve", json!({"board": blocks(), "bogus": 1}));
+    assert_eq!((status, &body["error"]), (400, &json!("bad_request")));
+    let (status, body) = server.post("/nope", json!({"board": blocks()}));
+    assert_eq!((status, &body["error"]), (404, &json!("not_found")));
+    let (status, _, _) = server.request("GET", "/solve", "");
+    assert_eq!(status, 405);
+}
+
+#[test]
+fn test_timeout() {
+    use serde_json::json;
+
+    //只有一个名额, 不限制局面数量时 idastar 要搜索很久
+    let server = Server::start(&["-j", "1", "-l", "18446744073709551615"]);
+    for algorithm in ["idastar", "bfs", "bidirectional", "astar", "parallel"] {
+        let (status, body) = server.post(
+            "/solve",
+            json!({"board": "45LZLZTajcEg", "algorithm": algorithm, "timeout_ms": 1}),
+        );
+        assert_eq!(
+            (status, &body["error"]),
+            (504, &json!("timeout")),
+            "{}",
+            algorithm
+        );
+        //超时的搜索被取消后归还名额
+        let start = std::time::Instant::now();
+        loop {
+            let (status, body) = server.post("/solve", json!({"board": blocks()}));
+            if status == 200 {
+                break;
+            }
+            assert_eq!(status, 503, "{}", body);
+            assert!(start.elapsed().as_secs() < 5, "{} still running", algorithm);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+}